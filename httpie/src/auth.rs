@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use reqwest::{Client, header, Request, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthType {
+    Basic,
+    Bearer,
+    Digest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Auth {
+    pub kind: AuthType,
+    pub user: String,
+    pub password: String,
+}
+
+impl Auth {
+    /// 解析 `--auth` 参数，basic/digest 缺少密码时在终端提示输入
+    pub fn new(kind: AuthType, s: &str) -> Result<Self> {
+        if kind == AuthType::Bearer {
+            return Ok(Self { kind, user: String::new(), password: s.into() });
+        }
+        let (user, password) = match s.split_once(':') {
+            Some((user, password)) => (user.to_string(), password.to_string()),
+            None => (s.to_string(), rpassword::prompt_password(format!("Password for {}: ", s))?),
+        };
+        Ok(Self { kind, user, password })
+    }
+
+    /// 在请求发出之前加上认证信息，digest 认证要等服务器的质询
+    pub fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+        match self.kind {
+            AuthType::Basic => req.basic_auth(&self.user, Some(&self.password)),
+            AuthType::Bearer => req.bearer_auth(&self.password),
+            AuthType::Digest => req,
+        }
+    }
+
+    /// 发送请求，digest 认证收到 401 后根据质询重新发送一次
+    pub async fn execute(&self, client: &Client, request: Request) -> Result<Response> {
+        if self.kind != AuthType::Digest {
+            return Ok(client.execute(request).await?);
+        }
+        let retry = request.try_clone();
+        let resp = client.execute(request).await?;
+        let challenge = match resp.headers().get(header::WWW_AUTHENTICATE) {
+            Some(v) if resp.status() == StatusCode::UNAUTHORIZED => v.to_str()?.to_string(),
+            _ => return Ok(resp),
+        };
+        let mut retry = retry.ok_or_else(|| anyhow!("Digest auth cannot resend a streaming body"))?;
+        let url = retry.url();
+        let uri = match url.query() {
+            Some(q) => format!("{}?{}", url.path(), q),
+            None => url.path().to_string(),
+        };
+        let value = digest_response(&challenge, &self.user, &self.password, retry.method().as_str(), &uri, &cnonce())?;
+        retry.headers_mut().insert(header::AUTHORIZATION, value.parse()?);
+        Ok(client.execute(retry).await?)
+    }
+}
+
+fn cnonce() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    format!("{:x}", md5::compute(nanos.to_string()))[..16].to_string()
+}
+
+fn md5_hex(s: &str) -> String {
+    format!("{:x}", md5::compute(s))
+}
+
+/// 解析 `WWW-Authenticate: Digest k="v", ...` 中的参数
+fn parse_challenge(challenge: &str) -> Result<HashMap<String, String>> {
+    let rest = challenge
+        .trim()
+        .strip_prefix("Digest ")
+        .ok_or_else(|| anyhow!("Server did not send a digest challenge: {}", challenge))?;
+    let mut params = HashMap::new();
+    let mut chars = rest.chars().peekable();
+    loop {
+        while matches!(chars.peek(), Some(c) if *c == ',' || c.is_whitespace()) {
+            chars.next();
+        }
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.is_empty() {
+            break;
+        }
+        let value: String = if chars.peek() == Some(&'"') {
+            chars.next();
+            chars.by_ref().take_while(|c| *c != '"').collect()
+        } else {
+            chars.by_ref().take_while(|c| *c != ',').collect()
+        };
+        params.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+    Ok(params)
+}
+
+/// 按 RFC 2617 计算 digest 认证的 `Authorization` 头
+fn digest_response(challenge: &str, user: &str, password: &str, method: &str, uri: &str, cnonce: &str) -> Result<String> {
+    let params = parse_challenge(challenge)?;
+    let get = |k: &str| params.get(k).map(|v| v.as_str()).unwrap_or_default();
+    let (realm, nonce) = (get("realm"), get("nonce"));
+    let algorithm = params.get("algorithm").map(|v| v.to_ascii_uppercase());
+    let mut ha1 = md5_hex(&format!("{}:{}:{}", user, realm, password));
+    match algorithm.as_deref() {
+        None | Some("MD5") => {}
+        Some("MD5-SESS") => ha1 = md5_hex(&format!("{}:{}:{}", ha1, nonce, cnonce)),
+        Some(v) => return Err(anyhow!("Unsupported digest algorithm {}", v)),
+    }
+    let ha2 = md5_hex(&format!("{}:{}", method, uri));
+    let qop = get("qop").split(',').map(|v| v.trim()).any(|v| v == "auth");
+    let nc = "00000001";
+    let response = if qop {
+        md5_hex(&format!("{}:{}:{}:{}:auth:{}", ha1, nonce, nc, cnonce, ha2))
+    } else {
+        md5_hex(&format!("{}:{}:{}", ha1, nonce, ha2))
+    };
+
+    let mut value = format!(
+        r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", response="{}""#,
+        user, realm, nonce, uri, response
+    );
+    if let Some(opaque) = params.get("opaque") {
+        value.push_str(&format!(r#", opaque="{}""#, opaque));
+    }
+    if let Some(algorithm) = params.get("algorithm") {
+        value.push_str(&format!(", algorithm={}", algorithm));
+    }
+    if qop {
+        value.push_str(&format!(r#", qop=auth, nc={}, cnonce="{}""#, nc, cnonce));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_new() {
+        let auth = Auth::new(AuthType::Basic, "user:pa:ss").unwrap();
+        assert_eq!((auth.user.as_str(), auth.password.as_str()), ("user", "pa:ss"));
+        let auth = Auth::new(AuthType::Bearer, "token:abc").unwrap();
+        assert_eq!(auth.password, "token:abc");
+    }
+
+    #[test]
+    fn test_digest_response() {
+        // RFC 2617 3.5 中的例子
+        let challenge = r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#;
+        let value = digest_response(challenge, "Mufasa", "Circle Of Life", "GET", "/dir/index.html", "0a4f113b").unwrap();
+        assert!(value.contains(r#"response="6629fae49393a05397450978507c4ef1""#));
+        assert!(value.contains(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+        assert!(value.contains(r#"qop=auth, nc=00000001, cnonce="0a4f113b""#));
+        assert!(digest_response("Basic realm=\"x\"", "u", "p", "GET", "/", "c").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use serde::Deserialize;
+use reqwest::StatusCode;
+use serde_json::Value;
+use crate::config::Config;
+use crate::expect::{self, Expect};
+use crate::items::KvItem;
+use crate::output::get_content_type;
+use crate::{build, jsonpath, parse_method, parse_url, send, status_exit_code, Args, Context, Opts, Run, EXIT_EXPECT};
+
+/// `run` 子命令执行的请求集合，支持 TOML 和 YAML 两种格式
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Collection {
+    /// 所有请求共用的变量，在请求里用 `{{name}}` 引用
+    #[serde(default)]
+    variables: BTreeMap<String, Value>,
+    /// 环境配置，例如 `[env.dev]`，用 `--env` 选择，会覆盖 `variables` 里的同名变量
+    #[serde(default)]
+    env: BTreeMap<String, BTreeMap<String, Value>>,
+    requests: Vec<Entry>,
+}
+
+/// 集合里的一个请求，字段和命令行参数一一对应
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    name: String,
+    #[serde(default = "default_method")]
+    method: String,
+    url: String,
+    /// 和命令行上的请求项语法相同，例如 `Header:value`、`field:=json`
+    #[serde(default)]
+    items: Vec<String>,
+    #[serde(default)]
+    form: bool,
+    #[serde(default)]
+    multipart: bool,
+    raw: Option<String>,
+    /// 变量名到 JSONPath 的映射，从响应里提取值给后面的请求使用
+    #[serde(default)]
+    extract: BTreeMap<String, String>,
+    /// 和 `--expect` 语法相同的断言，命令行上的断言对每个请求都生效
+    #[serde(default)]
+    expect: Vec<String>,
+}
+
+fn default_method() -> String {
+    "GET".into()
+}
+
+impl Collection {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let collection = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(|e| anyhow!(e.to_string())),
+            Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| anyhow!(e.to_string())),
+            _ => return Err(anyhow!("Unsupported collection {}, expected a .toml, .yaml or .yml file", path.display())),
+        };
+        collection.map_err(|e| anyhow!("Invalid collection {}: {}", path.display(), e))
+    }
+
+    /// 合并缺省变量和 `env` 指定的环境里的变量
+    fn variables(&self, env: Option<&str>) -> Result<BTreeMap<String, String>> {
+        let mut variables: BTreeMap<String, String> =
+            self.variables.iter().map(|(k, v)| (k.clone(), to_string(v))).collect();
+        if let Some(env) = env {
+            let profile = self.env.get(env).ok_or_else(|| anyhow!("Unknown environment {}", env))?;
+            variables.extend(profile.iter().map(|(k, v)| (k.clone(), to_string(v))));
+        }
+        Ok(variables)
+    }
+}
+
+impl Entry {
+    /// URL 里的变量先展开，再展开 host 别名
+    fn args(&self, variables: &BTreeMap<String, String>, config: &Config, scheme: &str) -> Result<Args> {
+        let items = self
+            .items
+            .iter()
+            .map(|item| expand(item, variables)?.parse())
+            .collect::<Result<Vec<KvItem>>>()?;
+        Ok(Args {
+            url: parse_url(&config.expand_alias(&expand(&self.url, variables)?), scheme)?,
+            items,
+            form: self.form,
+            multipart: self.multipart,
+            raw: self.raw.as_deref().map(|raw| expand(raw, variables)).transpose()?,
+            binary: None,
+        })
+    }
+
+    fn expects(&self) -> Result<Vec<Expect>> {
+        self.expect.iter().map(|e| e.parse()).collect()
+    }
+}
+
+/// 有断言的请求由断言决定成败，例如 `status=404`，这时不再缺省检查状态码
+fn exit_code(opts: &Opts, tty: bool, status: StatusCode, expected: bool) -> u8 {
+    if opts.check_status(tty, expected) { status_exit_code(status) } else { 0 }
+}
+
+/// 字符串变量直接使用，其他类型用 JSON 表示
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+/// 替换字符串里的 `{{name}}`
+fn expand(s: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let mut result = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").ok_or_else(|| anyhow!("Unclosed {{{{ in {}", s))? + start;
+        let name = rest[start + 2..end].trim();
+        let value = variables.get(name).ok_or_else(|| anyhow!("Undefined variable {} in {}", name, s))?;
+        result.push_str(&rest[..start]);
+        result.push_str(value);
+        rest = &rest[end + 2..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// 从 JSON 响应里提取第一个匹配的值
+fn extract(body: &[u8], path: &str) -> Result<String> {
+    let value: Value = serde_json::from_slice(body).map_err(|_| anyhow!("Cannot extract {} from a non-JSON response", path))?;
+    let found = jsonpath::select(&value, path)?;
+    let found = found.first().ok_or_else(|| anyhow!("{} matched nothing in the response", path))?;
+    Ok(to_string(found))
+}
+
+/// 按顺序执行集合里的请求，有请求失败时停止并返回对应的退出码，断言失败不会停止
+pub async fn run(opts: &Opts, run: &Run, config: &Config, ctx: &Context) -> Result<u8> {
+    let unsupported = [
+        ("--session", opts.session.is_some()),
+        ("--stream", opts.stream),
+        ("--download", opts.download || opts.output.is_some()),
+        ("--print-curl", opts.print_curl),
+    ];
+    if let Some((name, _)) = unsupported.iter().find(|(_, used)| *used) {
+        return Err(anyhow!("{} cannot be used with run", name));
+    }
+    let collection = Collection::load(&run.file)?;
+    for name in &run.names {
+        if !collection.requests.iter().any(|entry| &entry.name == name) {
+            return Err(anyhow!("No request named {} in {}", name, run.file.display()));
+        }
+    }
+    let mut variables = collection.variables(run.env.as_deref())?;
+    variables.extend(run.vars.iter().cloned());
+    let scheme = opts.default_scheme.as_deref().unwrap_or("http");
+    let entries = collection.requests.iter().filter(|entry| run.names.is_empty() || run.names.contains(&entry.name));
+    let mut passed = true;
+    for entry in entries {
+        eprintln!("{}", format!("==> {}", entry.name).bold());
+        let args = entry.args(&variables, config, scheme).map_err(|e| anyhow!("Request {}: {}", entry.name, e))?;
+        let expects = entry.expects().map_err(|e| anyhow!("Request {}: {}", entry.name, e))?;
+        let request = build(ctx, parse_method(&expand(&entry.method, &variables)?)?, &args)?;
+        ctx.output.print_request(&request)?;
+        if opts.offline {
+            continue;
+        }
+        let started = Instant::now();
+        let (resp, _) = send(ctx, request).await?;
+        let (status, headers) = (resp.status(), resp.headers().clone());
+        ctx.output.print_head(&resp);
+        let body = resp.bytes().await?;
+        let latency = started.elapsed();
+        ctx.output.print_content(get_content_type(&headers), &body)?;
+        let outcomes: Vec<_> = opts
+            .expect
+            .iter()
+            .chain(&expects)
+            .map(|e| e.check(status, &headers, &body, latency))
+            .collect();
+        if !outcomes.is_empty() {
+            passed &= expect::report(&outcomes);
+        }
+        for (name, path) in &entry.extract {
+            let value = extract(&body, path).map_err(|e| anyhow!("Request {}: {}", entry.name, e))?;
+            variables.insert(name.clone(), value);
+        }
+        let code = exit_code(opts, ctx.output.tty, status, !outcomes.is_empty());
+        if code != 0 {
+            eprintln!("{}", format!("Warning: HTTP {} from {}", status, entry.name).yellow());
+            return Ok(code);
+        }
+    }
+    Ok(if passed { 0 } else { EXIT_EXPECT })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    const COLLECTION: &str = r#"
+[variables]
+base = "http://localhost:3000"
+user = "ann"
+page = 1
+
+[env.prod]
+base = "https://api.example.com"
+
+[[requests]]
+name = "login"
+method = "POST"
+url = "{{base}}/login"
+items = ["name={{user}}", "page:={{ page }}"]
+extract = { token = "$.token" }
+
+[[requests]]
+name = "me"
+url = "{{base}}/me"
+items = ["Authorization:Bearer {{token}}"]
+"#;
+
+    #[test]
+    fn test_collection() {
+        let collection: Collection = toml::from_str(COLLECTION).unwrap();
+        let config: Config = toml::from_str("[aliases]\nlocal = \"http://127.0.0.1:3000\"").unwrap();
+        let variables = collection.variables(Some("prod")).unwrap();
+        assert_eq!(variables["base"], "https://api.example.com");
+        assert_eq!(variables["page"], "1");
+        assert!(collection.variables(Some("staging")).is_err());
+
+        let args = collection.requests[0].args(&variables, &config, "http").unwrap();
+        assert_eq!(args.url, "https://api.example.com/login");
+        assert_eq!(args.items[1], KvItem::Json("page".into(), Value::from(1)));
+        assert!(collection.requests[1].args(&variables, &config, "http").is_err());
+
+        let yaml = "requests:\n  - name: ping\n    url: :3000/ping\n  - name: alias\n    url: local/ping\n";
+        let collection: Collection = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(collection.requests[0].method, "GET");
+        assert_eq!(collection.requests[0].args(&BTreeMap::new(), &config, "http").unwrap().url, "http://localhost:3000/ping");
+        assert_eq!(collection.requests[1].args(&BTreeMap::new(), &config, "http").unwrap().url, "http://127.0.0.1:3000/ping");
+    }
+
+    #[test]
+    fn test_expected_status() {
+        let toml = "[[requests]]\nname = \"missing\"\nurl = \"/users/0\"\nexpect = [\"status=404\"]\n";
+        let collection: Collection = toml::from_str(toml).unwrap();
+        let expects = collection.requests[0].expects().unwrap();
+        let outcome = expects[0].check(StatusCode::NOT_FOUND, &Default::default(), b"", Default::default());
+        assert!(outcome.failure.is_none());
+        // stdout 被重定向时，预期中的 404 不会让 `run` 失败，没有断言的请求仍然检查状态码
+        let opts = Opts::parse_from(["httpie", "run", "c.toml"]);
+        assert_eq!(exit_code(&opts, false, StatusCode::NOT_FOUND, !expects.is_empty()), 0);
+        assert_eq!(exit_code(&opts, false, StatusCode::NOT_FOUND, false), 4);
+        let opts = Opts::parse_from(["httpie", "--check-status", "run", "c.toml"]);
+        assert_eq!(exit_code(&opts, false, StatusCode::NOT_FOUND, true), 4);
+    }
+
+    #[test]
+    fn test_expand_and_extract() {
+        let variables = BTreeMap::from([("id".to_string(), "7".to_string())]);
+        assert_eq!(expand("/users/{{id}}/{{ id }}", &variables).unwrap(), "/users/7/7");
+        assert!(expand("{{missing}}", &variables).is_err());
+        assert!(expand("{{id", &variables).is_err());
+        assert_eq!(extract(br#"{"token": "abc", "n": {"a": 1}}"#, "$.token").unwrap(), "abc");
+        assert_eq!(extract(br#"{"token": "abc", "n": {"a": 1}}"#, "$.n").unwrap(), r#"{"a":1}"#);
+        assert!(extract(b"<html>", "$.token").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+
+/// `~/.config/httpie-rs/config.toml` 里的缺省配置，命令行参数优先
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// 加在命令行参数之前的全局选项，例如 `["--follow", "--check-status"]`
+    pub default_options: Vec<String>,
+    /// 每个请求都会带上的请求头
+    pub headers: BTreeMap<String, String>,
+    pub style: Option<String>,
+    pub timeout: Option<f64>,
+    /// host 别名，`get api/users` 会展开成 `<aliases.api>/users`
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("httpie-rs").join("config.toml"))
+    }
+
+    /// 加载配置文件，文件不存在时使用空配置
+    pub fn load() -> Result<Self> {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Self::default()),
+        };
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| anyhow!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    /// 把配置合并到命令行参数里：配置项放在前面，这样命令行上的同名参数会覆盖它们
+    pub fn args(&self, argv: Vec<String>) -> Vec<String> {
+        let mut argv = argv.into_iter();
+        let mut args: Vec<String> = argv.next().into_iter().collect();
+        args.extend(self.default_options.iter().cloned());
+        if let Some(style) = &self.style {
+            args.push(format!("--style={}", style));
+        }
+        if let Some(timeout) = self.timeout {
+            args.push(format!("--timeout={}", timeout));
+        }
+        args.extend(argv);
+        args
+    }
+
+    /// 展开请求 URL 开头的 host 别名，只用于 URL，选项的值和请求项保持不变
+    pub fn expand_alias(&self, url: &str) -> String {
+        let (name, rest) = match url.split_once('/') {
+            Some((name, rest)) => (name, Some(rest)),
+            None => (url, None),
+        };
+        let base = match self.aliases.get(name) {
+            Some(base) => base.trim_end_matches('/'),
+            None => return url.to_string(),
+        };
+        match rest {
+            Some(rest) => format!("{}/{}", base, rest),
+            None => base.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+default_options = ["--follow"]
+style = "InspiredGitHub"
+timeout = 2.5
+
+[headers]
+X-Team = "core"
+
+[aliases]
+api = "https://api.example.com/v1/"
+"#;
+
+    #[test]
+    fn test_config_args() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        assert_eq!(config.headers.get("X-Team").unwrap(), "core");
+        let argv = ["httpie", "--session", "api", "get", "api/users", "q==api/x"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            config.args(argv),
+            vec![
+                "httpie",
+                "--follow",
+                "--style=InspiredGitHub",
+                "--timeout=2.5",
+                "--session",
+                "api",
+                "get",
+                "api/users",
+                "q==api/x",
+            ]
+        );
+        assert_eq!(config.expand_alias("api/users"), "https://api.example.com/v1/users");
+        assert_eq!(config.expand_alias("api"), "https://api.example.com/v1");
+        assert_eq!(config.expand_alias("apix/users"), "apix/users");
+        assert_eq!(config.expand_alias(":3000/api"), ":3000/api");
+        assert!(toml::from_str::<Config>("unknown = 1").is_err());
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::VecDeque;
+use std::fs;
+use anyhow::{Result, anyhow};
+use reqwest::{Method, Request, Url};
+use serde_json::Value;
+use crate::auth::AuthType;
+use crate::items::KvItem;
+use crate::tls::Verify;
+use crate::{Args, Custom, Opts, SubCommand};
+
+/// 把构造好的请求转换成等价的 curl 命令
+pub fn to_curl(request: &Request) -> Result<String> {
+    let mut words = vec!["curl".to_string()];
+    match *request.method() {
+        // 带 body 时 curl 缺省用 POST，所以 GET 也要写出来
+        Method::GET if request.body().is_none() => {}
+        Method::HEAD => words.push("--head".into()),
+        ref method => words.extend(["-X".into(), quote(method.as_str())]),
+    }
+    words.push(quote(request.url().as_str()));
+    for (name, value) in request.headers() {
+        let value = value.to_str().map_err(|_| anyhow!("Cannot export the non-ASCII header {}", name))?;
+        words.extend(["-H".into(), quote(&format!("{}: {}", name, value))]);
+    }
+    if let Some(body) = request.body() {
+        let body = body
+            .as_bytes()
+            .ok_or_else(|| anyhow!("Cannot export a streaming body, e.g. --multipart, as a curl command"))?;
+        let body = std::str::from_utf8(body).map_err(|_| anyhow!("Cannot export a binary body as a curl command"))?;
+        words.extend(["--data-raw".into(), quote(body)]);
+    }
+    Ok(words.join(" "))
+}
+
+/// 按 shell 的规则加上单引号
+fn quote(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c)) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+/// 从 curl 命令里解析出来的请求
+#[derive(Debug, Default)]
+pub struct Curl {
+    method: Option<Method>,
+    url: Option<String>,
+    headers: Vec<(String, String)>,
+    /// `-d` 系列参数，按 curl 的规则用 `&` 连接
+    data: Vec<Vec<u8>>,
+    /// `-F` 参数
+    parts: Vec<KvItem>,
+    get: bool,
+    head: bool,
+    user: Option<String>,
+    auth_type: Option<AuthType>,
+    insecure: bool,
+    location: bool,
+}
+
+// 不带值的短选项，可以合在一起写，例如 `-sSL`
+const SHORT_FLAGS: [&str; 8] = ["-G", "-I", "-k", "-L", "-s", "-S", "-v", "-i"];
+
+/// 解析浏览器开发者工具里复制出来的 curl 命令，不支持的选项会报错
+pub fn parse(command: &str) -> Result<Curl> {
+    let mut words: VecDeque<String> = split_words(command)?.into();
+    if words.front().map(String::as_str) == Some("curl") {
+        words.pop_front();
+    }
+    let mut curl = Curl::default();
+    while let Some(word) = words.pop_front() {
+        if !word.starts_with('-') || word == "-" {
+            curl.url = Some(word);
+            continue;
+        }
+        // 短选项的值可以直接跟在后面，例如 `-XPOST`
+        let (flag, mut attached) = if word.starts_with("--") || word.len() <= 2 || !word.is_char_boundary(2) {
+            (word.as_str(), None)
+        } else {
+            let (flag, attached) = word.split_at(2);
+            (flag, Some(attached.to_string()))
+        };
+        // 合在一起的短选项，剩下的部分当作下一个选项处理
+        if SHORT_FLAGS.contains(&flag) {
+            if let Some(rest) = attached.take() {
+                words.push_front(format!("-{}", rest));
+            }
+        }
+        let mut value = || {
+            attached
+                .clone()
+                .or_else(|| words.pop_front())
+                .ok_or_else(|| anyhow!("Missing value for curl option {}", flag))
+        };
+        match flag {
+            "-X" | "--request" => curl.method = Some(Method::from_bytes(value()?.to_ascii_uppercase().as_bytes())?),
+            "--url" => curl.url = Some(value()?),
+            "-H" | "--header" => {
+                let header = value()?;
+                let (name, value) = header.split_once(':').ok_or_else(|| anyhow!("Invalid curl header {}", header))?;
+                curl.headers.push((name.trim().into(), value.trim().into()));
+            }
+            "-A" | "--user-agent" => curl.headers.push(("User-Agent".into(), value()?)),
+            "-e" | "--referer" => curl.headers.push(("Referer".into(), value()?)),
+            "-b" | "--cookie" => curl.headers.push(("Cookie".into(), value()?)),
+            "-d" | "--data" | "--data-ascii" => curl.data.push(read_data(&value()?, false)?),
+            "--data-binary" => curl.data.push(read_data(&value()?, true)?),
+            "--data-raw" => curl.data.push(value()?.into_bytes()),
+            "--data-urlencode" => curl.data.push(urlencode_data(&value()?)?.into_bytes()),
+            "-F" | "--form" => curl.parts.push(parse_part(&value()?)?),
+            "-u" | "--user" => curl.user = Some(value()?),
+            "--oauth2-bearer" => {
+                curl.user = Some(value()?);
+                curl.auth_type = Some(AuthType::Bearer);
+            }
+            "--digest" => curl.auth_type = Some(AuthType::Digest),
+            "--basic" => curl.auth_type = Some(AuthType::Basic),
+            "-G" | "--get" => curl.get = true,
+            "-I" | "--head" => curl.head = true,
+            "-k" | "--insecure" => curl.insecure = true,
+            "-L" | "--location" => curl.location = true,
+            // 不影响请求内容的选项直接忽略，`--compressed` 对应的 Accept-Encoding 也不会发送
+            "--compressed" | "-s" | "--silent" | "-S" | "--show-error" | "-v" | "--verbose" | "-i" | "--include"
+            | "--http1.1" | "--http2" => {}
+            _ => return Err(anyhow!("Unsupported curl option {}", word)),
+        }
+    }
+    Ok(curl)
+}
+
+/// `-d @file` 从文件读入 body，和 curl 一样去掉换行，`--data-binary @file` 原样读入
+fn read_data(value: &str, binary: bool) -> Result<Vec<u8>> {
+    match value.strip_prefix('@') {
+        Some(path) => {
+            let mut data = fs::read(path).map_err(|e| anyhow!("Failed to read {}: {}", path, e))?;
+            if !binary {
+                data.retain(|b| *b != b'\r' && *b != b'\n');
+            }
+            Ok(data)
+        }
+        None => Ok(value.as_bytes().to_vec()),
+    }
+}
+
+/// `--data-urlencode name=content`，只对 `=` 后面的内容编码
+fn urlencode_data(value: &str) -> Result<String> {
+    let (name, content) = value.split_once('=').unwrap_or(("", value));
+    let mut url = Url::parse("http://localhost/")?;
+    url.query_pairs_mut().append_pair(name, content);
+    let encoded = url.query().unwrap_or_default().to_string();
+    Ok(if name.is_empty() { encoded.trim_start_matches('=').to_string() } else { encoded })
+}
+
+/// `-F name=value` 和 `-F name=@path`，忽略 `;type=` 之类的附加属性
+fn parse_part(value: &str) -> Result<KvItem> {
+    let (name, content) = value.split_once('=').ok_or_else(|| anyhow!("Invalid curl form field {}", value))?;
+    Ok(match content.strip_prefix('@') {
+        Some(path) => KvItem::File(name.into(), path.split(';').next().unwrap_or_default().into()),
+        None => KvItem::Data(name.into(), content.into()),
+    })
+}
+
+impl Curl {
+    /// 转换成 `request` 子命令，`-u`、`-k` 和 `-L` 对应到全局选项上
+    pub fn apply(self, opts: &mut Opts) -> Result<()> {
+        let url = self.url.ok_or_else(|| anyhow!("No URL in the curl command"))?;
+        if !self.data.is_empty() && !self.parts.is_empty() {
+            return Err(anyhow!("curl -d and -F cannot be combined"));
+        }
+        let content_type = self.headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+        // 我们不解压响应，带上开发者工具里的 Accept-Encoding 会收到压缩过的 body
+        let mut items: Vec<KvItem> = self
+            .headers
+            .into_iter()
+            .filter(|(name, _)| !name.eq_ignore_ascii_case("accept-encoding"))
+            .map(|(k, v)| KvItem::Header(k, v))
+            .collect();
+        let (mut raw, mut binary, mut form) = (None, None, false);
+        let multipart = !self.parts.is_empty();
+        let has_body = !self.data.is_empty() || multipart;
+        if !self.data.is_empty() {
+            let data = self.data.join(&b'&');
+            if self.get {
+                let data = String::from_utf8(data).map_err(|_| anyhow!("curl -G cannot send binary data"))?;
+                let mut url = Url::parse("http://localhost/")?;
+                url.set_query(Some(&data));
+                items.extend(url.query_pairs().map(|(k, v)| KvItem::Query(k.into(), v.into())));
+            } else if let Ok(Value::Object(map)) = serde_json::from_slice(&data) {
+                items.extend(map.into_iter().map(|(k, v)| KvItem::Json(k, v)));
+            } else {
+                // curl 缺省用表单的 Content-Type 发送 `-d` 的内容
+                form = !content_type;
+                match String::from_utf8(data) {
+                    Ok(data) => raw = Some(data),
+                    Err(e) => binary = Some(e.into_bytes()),
+                }
+            }
+        }
+        items.extend(self.parts);
+        let method = self.method.unwrap_or(match (self.head, self.get, has_body) {
+            (true, ..) => Method::HEAD,
+            (_, false, true) => Method::POST,
+            _ => Method::GET,
+        });
+        opts.subcmd = SubCommand::Request(Custom { method, args: Args { url, items, form, multipart, raw, binary } });
+        if let Some(user) = self.user {
+            opts.auth = Some(user);
+        }
+        if let Some(auth_type) = self.auth_type {
+            opts.auth_type = auth_type;
+        }
+        if self.insecure {
+            opts.verify = Verify::No;
+        }
+        opts.follow |= self.location;
+        Ok(())
+    }
+}
+
+/// 按 shell 的规则拆分命令行，支持单引号、双引号、`$'...'`、反斜杠转义和续行
+fn split_words(s: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(anyhow!("Unterminated quote in the curl command")),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('t') => current.push('\t'),
+                            Some('r') => current.push('\r'),
+                            Some(c) => current.push(c),
+                            None => return Err(anyhow!("Unterminated quote in the curl command")),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(anyhow!("Unterminated quote in the curl command")),
+                    }
+                }
+            }
+            '"' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c) if "\"\\$`".contains(c) => current.push(c),
+                            Some(c) => current.extend(['\\', c]),
+                            None => return Err(anyhow!("Unterminated quote in the curl command")),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(anyhow!("Unterminated quote in the curl command")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // 行尾的反斜杠表示续行
+                Some('\n') | None => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_split_words() {
+        let words = split_words("curl 'https://a.com/x?a=1' \\\n  -H \"X-A: \\\"b\\\"\" --data-raw $'{\"a\":\\'b\\'}' ''").unwrap();
+        assert_eq!(words, vec!["curl", "https://a.com/x?a=1", "-H", "X-A: \"b\"", "--data-raw", "{\"a\":'b'}", ""]);
+        assert!(split_words("curl 'https://a.com").is_err());
+    }
+
+    #[test]
+    fn test_import_curl() {
+        let command = r#"curl 'https://a.com/users' -H 'Content-Type: application/json' --data-raw '{"name":"a","age":1}' -u user:pass -k"#;
+        let mut opts = Opts::parse_from(["httpie", "import-curl", command]);
+        parse(command).unwrap().apply(&mut opts).unwrap();
+        assert_eq!(opts.subcmd.method().unwrap(), Method::POST);
+        let args = opts.subcmd.args().unwrap();
+        assert_eq!(args.url, "https://a.com/users");
+        assert!(args.items.contains(&KvItem::Json("age".into(), Value::from(1))));
+        assert!(args.raw.is_none());
+        assert_eq!(opts.auth.as_deref(), Some("user:pass"));
+        assert_eq!(opts.verify, Verify::No);
+
+        let command = "curl -XPUT https://a.com/form -d a=1 -d b=2 -H 'Accept-Encoding: gzip, br' --compressed";
+        parse(command).unwrap().apply(&mut opts).unwrap();
+        assert_eq!(opts.subcmd.method().unwrap(), Method::PUT);
+        assert_eq!(opts.subcmd.args().unwrap().raw.as_deref(), Some("a=1&b=2"));
+        assert!(opts.subcmd.args().unwrap().form);
+        assert!(opts.subcmd.args().unwrap().items.is_empty());
+
+        parse("curl -G https://a.com -d 'q=a%20b'").unwrap().apply(&mut opts).unwrap();
+        assert_eq!(opts.subcmd.method().unwrap(), Method::GET);
+        assert_eq!(opts.subcmd.args().unwrap().items, vec![KvItem::Query("q".into(), "a b".into())]);
+
+        // 只有 `-d` 会去掉文件里的换行，`--data-binary` 原样发送，也可以不是 UTF-8
+        let path = std::env::temp_dir().join("httpie-curl-data-binary.txt");
+        fs::write(&path, b"{\"a\":1}\r\n{\"b\":2}\n").unwrap();
+        parse(&format!("curl https://a.com -d @{}", path.display())).unwrap().apply(&mut opts).unwrap();
+        assert_eq!(opts.subcmd.args().unwrap().raw.as_deref(), Some("{\"a\":1}{\"b\":2}"));
+        parse(&format!("curl https://a.com --data-binary @{}", path.display())).unwrap().apply(&mut opts).unwrap();
+        assert_eq!(opts.subcmd.args().unwrap().raw.as_deref(), Some("{\"a\":1}\r\n{\"b\":2}\n"));
+        fs::write(&path, b"\xff\x00\n").unwrap();
+        parse(&format!("curl https://a.com --data-binary @{}", path.display())).unwrap().apply(&mut opts).unwrap();
+        assert_eq!(opts.subcmd.args().unwrap().binary.as_deref(), Some(&b"\xff\x00\n"[..]));
+        fs::remove_file(&path).unwrap();
+        assert!(parse("curl --proxy-anyauth https://a.com").is_err());
+
+        // 合在一起的短选项
+        let mut opts = Opts::parse_from(["httpie", "import-curl", "-"]);
+        parse("curl -sSL https://a.com").unwrap().apply(&mut opts).unwrap();
+        assert!(opts.follow);
+        let mut opts = Opts::parse_from(["httpie", "import-curl", "-"]);
+        parse("curl -kL https://a.com").unwrap().apply(&mut opts).unwrap();
+        assert!(opts.follow);
+        assert_eq!(opts.verify, Verify::No);
+        parse("curl -sXPUT https://a.com").unwrap().apply(&mut opts).unwrap();
+        assert_eq!(opts.subcmd.method().unwrap(), Method::PUT);
+        assert!(parse("curl -sZ https://a.com").is_err());
+    }
+
+    #[test]
+    fn test_to_curl() {
+        let mut request = Request::new(Method::POST, "https://a.com/x?a=1&b=2".parse().unwrap());
+        request.headers_mut().insert("content-type", "application/json".parse().unwrap());
+        *request.body_mut() = Some(r#"{"a":"it's"}"#.into());
+        assert_eq!(
+            to_curl(&request).unwrap(),
+            r#"curl -X POST 'https://a.com/x?a=1&b=2' -H 'content-type: application/json' --data-raw '{"a":"it'\''s"}'"#
+        );
+
+        let mut request = Request::new(Method::GET, "https://a.com/search".parse().unwrap());
+        assert_eq!(to_curl(&request).unwrap(), "curl https://a.com/search");
+        *request.body_mut() = Some("q".into());
+        assert_eq!(to_curl(&request).unwrap(), "curl -X GET https://a.com/search --data-raw q");
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::{header, Response, StatusCode};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+
+/// `--continue` 时已经下载的字节数，文件不存在时返回 `None`
+pub fn resume_offset(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|m| m.len()).filter(|len| *len > 0)
+}
+
+/// 把响应 body 边下载边写入文件，并在 stderr 上显示进度
+pub async fn download(mut resp: Response, output: Option<&Path>) -> Result<()> {
+    let partial = resp.status() == StatusCode::PARTIAL_CONTENT;
+    let path = match output {
+        Some(path) => path.to_path_buf(),
+        None => unique_path(filename(&resp)),
+    };
+    // 服务器返回 206 时才续传，否则从头开始写
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(partial)
+        .truncate(!partial)
+        .open(&path)
+        .await?;
+    let offset = if partial { file.metadata().await?.len() } else { 0 };
+
+    let bar = match resp.content_length() {
+        Some(len) => ProgressBar::new(offset + len).with_style(ProgressStyle::with_template(
+            "{wide_bar} {bytes}/{total_bytes} {bytes_per_sec} eta {eta}",
+        )?),
+        None => ProgressBar::new_spinner().with_style(ProgressStyle::with_template("{spinner} {bytes} {bytes_per_sec}")?),
+    };
+    bar.set_position(offset);
+    while let Some(chunk) = resp.chunk().await? {
+        file.write_all(&chunk).await?;
+        bar.inc(chunk.len() as u64);
+    }
+    file.flush().await?;
+    bar.finish();
+    eprintln!("Downloaded to {}", path.display());
+    Ok(())
+}
+
+/// 优先使用 `Content-Disposition` 里的文件名，其次是 URL 的最后一段
+fn filename(resp: &Response) -> String {
+    let from_header = resp
+        .headers()
+        .get(header::CONTENT_DISPOSITION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(disposition_filename);
+    let name = from_header.or_else(|| {
+        let segment = resp.url().path_segments()?.next_back()?;
+        if segment.is_empty() { None } else { Some(segment.to_string()) }
+    });
+    // 只保留文件名部分，避免写到当前目录之外
+    name.as_deref()
+        .and_then(|name| Path::new(name).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| default_filename(resp))
+}
+
+fn default_filename(resp: &Response) -> String {
+    let host = resp.url().host_str().unwrap_or("index").to_string();
+    let ext = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok()?.parse::<mime::Mime>().ok())
+        .and_then(|m| mime_guess::get_mime_extensions(&m)?.first().copied());
+    match ext {
+        Some(ext) => format!("{}.{}", host, ext),
+        None => host,
+    }
+}
+
+fn disposition_filename(value: &str) -> Option<String> {
+    let params: Vec<(&str, &str)> = value
+        .split(';')
+        .filter_map(|p| p.trim().split_once('='))
+        .map(|(k, v)| (k.trim(), v.trim().trim_matches('"')))
+        .collect();
+    // `filename*=UTF-8''name` 优先于 `filename=name`
+    let extended = params
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("filename*"))
+        .and_then(|(_, v)| v.split_once("''"))
+        .map(|(_, v)| percent_decode(v));
+    extended.or_else(|| {
+        params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("filename"))
+            .map(|(_, v)| v.to_string())
+    })
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// 文件已经存在时依次尝试 `name-1`、`name-2`
+fn unique_path(name: String) -> PathBuf {
+    let path = PathBuf::from(&name);
+    if !path.exists() {
+        return path;
+    }
+    (1..)
+        .map(|i| PathBuf::from(format!("{}-{}", name, i)))
+        .find(|p| !p.exists())
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disposition_filename() {
+        assert_eq!(disposition_filename(r#"attachment; filename="a.zip""#).unwrap(), "a.zip");
+        assert_eq!(disposition_filename("attachment; filename=a.zip").unwrap(), "a.zip");
+        assert_eq!(
+            disposition_filename(r#"attachment; filename="a.zip"; filename*=UTF-8''%E4%BD%A0%E5%A5%BD.zip"#).unwrap(),
+            "你好.zip"
+        );
+        assert!(disposition_filename("inline").is_none());
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let dir = std::env::temp_dir().join("httpie_download_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let name = dir.join("file.bin");
+        std::fs::write(&name, b"x").unwrap();
+        let _ = std::fs::remove_file(dir.join("file.bin-1"));
+        assert_eq!(unique_path(name.to_string_lossy().into_owned()), dir.join("file.bin-1"));
+        assert_eq!(resume_offset(&name), Some(1));
+        assert_eq!(resume_offset(&dir.join("missing")), None);
+    }
+}
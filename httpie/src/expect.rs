@@ -0,0 +1,189 @@
+use std::str::FromStr;
+use std::time::Duration;
+use anyhow::anyhow;
+use colored::Colorize;
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde_json::Value;
+use crate::jsonpath;
+
+/// `--expect` 参数，对响应做的一个断言
+#[derive(Debug, Clone)]
+pub struct Expect {
+    /// 命令行上的原始写法，用在报告里
+    source: String,
+    check: Check,
+}
+
+#[derive(Debug, Clone)]
+enum Check {
+    /// `status=200`、`status=2xx` 或 `status=200-299`
+    Status(u16, u16),
+    /// `header:Name` 或 `header:Name~regex`
+    Header(String, Option<Regex>),
+    /// `$.path=json`，值不是合法 JSON 时当作字符串
+    Json(String, Value),
+    /// `body:text`
+    Body(String),
+    /// `latency<500ms`
+    Latency(Duration),
+}
+
+impl FromStr for Expect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || {
+            anyhow!(
+                "Invalid expectation {}, expected status=CODE, header:NAME[~REGEX], $.path=JSON, body:TEXT or latency<DURATION",
+                s
+            )
+        };
+        let check = if let Some(status) = s.strip_prefix("status=") {
+            let (min, max) = parse_status(status).ok_or_else(err)?;
+            Check::Status(min, max)
+        } else if let Some(header) = s.strip_prefix("header:") {
+            let (name, regex) = match header.split_once('~') {
+                Some((name, regex)) => (name, Some(Regex::new(regex)?)),
+                None => (header, None),
+            };
+            Check::Header(name.trim().into(), regex)
+        } else if let Some(text) = s.strip_prefix("body:") {
+            Check::Body(text.into())
+        } else if let Some(latency) = s.strip_prefix("latency<") {
+            Check::Latency(parse_duration(latency).ok_or_else(err)?)
+        } else if s.starts_with('$') {
+            let (path, value) = split_path(s).ok_or_else(err)?;
+            // 提前检查 JSONPath 的语法
+            jsonpath::select(&Value::Null, path)?;
+            let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.into()));
+            Check::Json(path.into(), value)
+        } else {
+            return Err(err());
+        };
+        Ok(Self { source: s.into(), check })
+    }
+}
+
+fn parse_status(s: &str) -> Option<(u16, u16)> {
+    if let Some(class) = s.strip_suffix("xx").and_then(|c| c.parse::<u16>().ok()).filter(|c| (1..=5).contains(c)) {
+        return Some((class * 100, class * 100 + 99));
+    }
+    match s.split_once('-') {
+        Some((min, max)) => Some((min.parse().ok()?, max.parse().ok()?)),
+        None => s.parse().ok().map(|code| (code, code)),
+    }
+}
+
+/// `500ms`、`1.5s`，不带单位时是毫秒
+fn parse_duration(s: &str) -> Option<Duration> {
+    let (value, scale) = match s.strip_suffix("ms") {
+        Some(ms) => (ms, 0.001),
+        None => s.strip_suffix('s').map_or((s, 0.001), |secs| (secs, 1.0)),
+    };
+    Duration::try_from_secs_f64(value.trim().parse::<f64>().ok()? * scale).ok()
+}
+
+/// 在 `[...]` 之外的第一个 `=` 处分开 JSONPath 和期望的值
+fn split_path(s: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '=' if depth == 0 => return Some((&s[..i], &s[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 一个断言的检查结果，失败时带上实际的值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub source: String,
+    pub failure: Option<String>,
+}
+
+impl Expect {
+    pub fn check(&self, status: StatusCode, headers: &HeaderMap, body: &[u8], latency: Duration) -> Outcome {
+        let failure = match &self.check {
+            Check::Status(min, max) => {
+                (!(*min..=*max).contains(&status.as_u16())).then(|| format!("got {}", status.as_u16()))
+            }
+            Check::Header(name, regex) => match (headers.get(name).map(|v| v.to_str().unwrap_or_default()), regex) {
+                (None, _) => Some("header is missing".into()),
+                (Some(value), Some(regex)) if !regex.is_match(value) => Some(format!("got {:?}", value)),
+                _ => None,
+            },
+            Check::Json(path, expected) => match serde_json::from_slice::<Value>(body) {
+                Ok(value) => {
+                    let found = jsonpath::select(&value, path).unwrap_or_default();
+                    match found.first() {
+                        None => Some("no match".into()),
+                        _ if found.contains(&expected) => None,
+                        Some(first) => Some(format!("got {}", first)),
+                    }
+                }
+                Err(_) => Some("body is not JSON".into()),
+            },
+            Check::Body(text) => {
+                (!String::from_utf8_lossy(body).contains(text.as_str())).then(|| "text not found in body".into())
+            }
+            Check::Latency(max) => (latency > *max).then(|| format!("took {}ms", latency.as_millis())),
+        };
+        Outcome { source: self.source.clone(), failure }
+    }
+}
+
+/// 在 stderr 上输出检查结果，全部通过时返回 `true`
+pub fn report(outcomes: &[Outcome]) -> bool {
+    for outcome in outcomes {
+        match &outcome.failure {
+            None => eprintln!("{} {}", "PASS".green(), outcome.source),
+            Some(failure) => eprintln!("{} {}: {}", "FAIL".red(), outcome.source, failure),
+        }
+    }
+    let failed = outcomes.iter().filter(|o| o.failure.is_some()).count();
+    let summary = format!("{} passed, {} failed", outcomes.len() - failed, failed);
+    eprintln!("{}", if failed == 0 { summary.green() } else { summary.red() });
+    failed == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(expect: &str) -> Option<String> {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json; charset=utf-8".parse().unwrap());
+        let body = br#"{"data": {"id": 5, "tags": ["a", "b"], "name": "ann"}}"#;
+        let expect: Expect = expect.parse().unwrap();
+        expect.check(StatusCode::CREATED, &headers, body, Duration::from_millis(120)).failure
+    }
+
+    #[test]
+    fn test_expect() {
+        assert_eq!(check("status=2xx"), None);
+        assert_eq!(check("status=200-204"), None);
+        assert_eq!(check("status=200"), Some("got 201".into()));
+        assert_eq!(check("header:Content-Type"), None);
+        assert_eq!(check("header:content-type~^application/json"), None);
+        assert_eq!(check("header:content-type~xml"), Some("got \"application/json; charset=utf-8\"".into()));
+        assert_eq!(check("header:etag"), Some("header is missing".into()));
+        assert_eq!(check("$.data.id=5"), None);
+        assert_eq!(check("$.data.id=6"), Some("got 5".into()));
+        assert_eq!(check("$.data.name=ann"), None);
+        assert_eq!(check("$.data.tags[*]=\"b\""), None);
+        assert_eq!(check("$.data.missing=1"), Some("no match".into()));
+        assert_eq!(check("body:\"ann\""), None);
+        assert_eq!(check("latency<1s"), None);
+        assert_eq!(check("latency<100ms"), Some("took 120ms".into()));
+        assert!("status=9xx".parse::<Expect>().is_err());
+        assert!("latency<fast".parse::<Expect>().is_err());
+        assert!("header:x~(".parse::<Expect>().is_err());
+        assert!("$.data".parse::<Expect>().is_err());
+        assert!("size=1".parse::<Expect>().is_err());
+    }
+}
@@ -0,0 +1,450 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+/// `--filter` 参数，jq 语法的一个子集：
+/// `.a.b`、`."a b"`、`.[0]`、`.[-1]`、`.[1:3]`、`.[]`、`|`、`keys`、`length`、`map(f)`、`select(f)`
+/// 以及 `==`、`!=`、`<`、`<=`、`>`、`>=` 比较
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter(Expr);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Identity,
+    Literal(Value),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, i64),
+    Slice(Box<Expr>, Option<i64>, Option<i64>),
+    Iterate(Box<Expr>),
+    Keys,
+    Length,
+    Map(Box<Expr>),
+    Select(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Colon,
+    Pipe,
+    Op(Op),
+    Ident(String),
+    Literal(Value),
+}
+
+pub fn parse(s: &str) -> Result<Filter> {
+    let mut tokens = tokenize(s)?.into_iter().peekable();
+    let expr = parse_pipe(&mut tokens)?;
+    match tokens.next() {
+        Some(token) => Err(anyhow!("Unexpected {:?} in filter {}", token, s)),
+        None => Ok(Filter(expr)),
+    }
+}
+
+impl Filter {
+    /// 和 jq 一样，一个输入可以产生零个或多个输出
+    pub fn apply(&self, value: &Value) -> Result<Vec<Value>> {
+        eval(&self.0, value)
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '.' => Token::Dot,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ':' => Token::Colon,
+            '|' => Token::Pipe,
+            '=' | '!' | '<' | '>' => {
+                let eq = chars.next_if(|(_, c)| *c == '=').is_some();
+                Token::Op(match (c, eq) {
+                    ('=', true) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => return Err(anyhow!("Unexpected {} in filter {}", c, s)),
+                })
+            }
+            '"' => {
+                // 字符串按 JSON 的规则解析转义
+                let mut escaped = false;
+                let end = chars
+                    .by_ref()
+                    .find(|(_, c)| {
+                        let end = *c == '"' && !escaped;
+                        escaped = *c == '\\' && !escaped;
+                        end
+                    })
+                    .map(|(j, _)| j)
+                    .ok_or_else(|| anyhow!("Unterminated string in filter {}", s))?;
+                Token::Literal(serde_json::from_str(&s[i..=end])?)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut end = i + 1;
+                while let Some((j, _)) = chars.next_if(|(_, c)| c.is_ascii_digit() || *c == '.') {
+                    end = j + 1;
+                }
+                let number: serde_json::Number =
+                    s[i..end].parse().map_err(|_| anyhow!("Invalid number {} in filter {}", &s[i..end], s))?;
+                Token::Literal(Value::Number(number))
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = i + 1;
+                while let Some((j, _)) = chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_') {
+                    end = j + 1;
+                }
+                Token::Ident(s[i..end].into())
+            }
+            c => return Err(anyhow!("Unexpected {} in filter {}", c, s)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+type Tokens = Peekable<IntoIter<Token>>;
+
+fn expect(tokens: &mut Tokens, expected: Token) -> Result<()> {
+    match tokens.next() {
+        Some(token) if token == expected => Ok(()),
+        token => Err(anyhow!("Expected {:?} in filter but found {:?}", expected, token)),
+    }
+}
+
+fn parse_pipe(tokens: &mut Tokens) -> Result<Expr> {
+    let mut expr = parse_compare(tokens)?;
+    while tokens.next_if_eq(&Token::Pipe).is_some() {
+        expr = Expr::Pipe(Box::new(expr), Box::new(parse_compare(tokens)?));
+    }
+    Ok(expr)
+}
+
+fn parse_compare(tokens: &mut Tokens) -> Result<Expr> {
+    let lhs = parse_postfix(tokens)?;
+    match tokens.peek() {
+        Some(Token::Op(op)) => {
+            let op = *op;
+            tokens.next();
+            Ok(Expr::Compare(Box::new(lhs), op, Box::new(parse_postfix(tokens)?)))
+        }
+        _ => Ok(lhs),
+    }
+}
+
+fn parse_postfix(tokens: &mut Tokens) -> Result<Expr> {
+    let mut expr = match tokens.next() {
+        Some(Token::Dot) => match tokens.peek() {
+            Some(Token::Ident(_) | Token::Literal(Value::String(_))) => parse_field(Expr::Identity, tokens)?,
+            Some(Token::LBracket) => {
+                tokens.next();
+                parse_bracket(Expr::Identity, tokens)?
+            }
+            _ => Expr::Identity,
+        },
+        Some(Token::Literal(value)) => Expr::Literal(value),
+        Some(Token::LParen) => {
+            let expr = parse_pipe(tokens)?;
+            expect(tokens, Token::RParen)?;
+            expr
+        }
+        Some(Token::Ident(name)) => match name.as_str() {
+            "keys" => Expr::Keys,
+            "length" => Expr::Length,
+            "true" => Expr::Literal(Value::Bool(true)),
+            "false" => Expr::Literal(Value::Bool(false)),
+            "null" => Expr::Literal(Value::Null),
+            "map" | "select" => {
+                expect(tokens, Token::LParen)?;
+                let arg = Box::new(parse_pipe(tokens)?);
+                expect(tokens, Token::RParen)?;
+                if name == "map" { Expr::Map(arg) } else { Expr::Select(arg) }
+            }
+            _ => return Err(anyhow!("Unsupported filter function {}", name)),
+        },
+        token => return Err(anyhow!("Unexpected {:?} in filter", token)),
+    };
+    loop {
+        expr = match tokens.peek() {
+            Some(Token::Dot) => {
+                tokens.next();
+                match tokens.next_if_eq(&Token::LBracket) {
+                    Some(_) => parse_bracket(expr, tokens)?,
+                    None => parse_field(expr, tokens)?,
+                }
+            }
+            Some(Token::LBracket) => {
+                tokens.next();
+                parse_bracket(expr, tokens)?
+            }
+            _ => return Ok(expr),
+        };
+    }
+}
+
+/// `.name` 或 `."name"`，调用时已经读掉了 `.`
+fn parse_field(expr: Expr, tokens: &mut Tokens) -> Result<Expr> {
+    match tokens.next() {
+        Some(Token::Ident(name)) => Ok(Expr::Field(Box::new(expr), name)),
+        Some(Token::Literal(Value::String(name))) => Ok(Expr::Field(Box::new(expr), name)),
+        token => Err(anyhow!("Expected a field name in filter but found {:?}", token)),
+    }
+}
+
+/// `[]`、`["name"]`、`[n]` 和 `[n:m]`，调用时已经读掉了 `[`
+fn parse_bracket(expr: Expr, tokens: &mut Tokens) -> Result<Expr> {
+    let expr = Box::new(expr);
+    let index = |token: Option<Token>| match token {
+        Some(Token::Literal(Value::Number(n))) => n.as_i64().ok_or_else(|| anyhow!("Index {} is not an integer", n)),
+        token => Err(anyhow!("Expected an index in filter but found {:?}", token)),
+    };
+    let result = match tokens.next() {
+        Some(Token::RBracket) => return Ok(Expr::Iterate(expr)),
+        Some(Token::Literal(Value::String(name))) => Expr::Field(expr, name),
+        Some(Token::Colon) => Expr::Slice(expr, None, Some(index(tokens.next())?)),
+        token => {
+            let start = index(token)?;
+            match tokens.next_if_eq(&Token::Colon) {
+                None => Expr::Index(expr, start),
+                Some(_) if tokens.peek() == Some(&Token::RBracket) => Expr::Slice(expr, Some(start), None),
+                Some(_) => Expr::Slice(expr, Some(start), Some(index(tokens.next())?)),
+            }
+        }
+    };
+    expect(tokens, Token::RBracket)?;
+    Ok(result)
+}
+
+fn eval(expr: &Expr, input: &Value) -> Result<Vec<Value>> {
+    let each = |inner: &Expr, f: &dyn Fn(Value) -> Result<Vec<Value>>| -> Result<Vec<Value>> {
+        let mut output = Vec::new();
+        for value in eval(inner, input)? {
+            output.extend(f(value)?);
+        }
+        Ok(output)
+    };
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::Literal(value) => Ok(vec![value.clone()]),
+        Expr::Field(inner, name) => each(inner, &|value| match value {
+            Value::Object(mut map) => Ok(vec![map.remove(name).unwrap_or(Value::Null)]),
+            Value::Null => Ok(vec![Value::Null]),
+            v => Err(anyhow!("Cannot index {} with \"{}\"", type_name(&v), name)),
+        }),
+        Expr::Index(inner, i) => each(inner, &|value| match value {
+            Value::Array(list) => Ok(vec![position(*i, list.len()).and_then(|i| list.get(i).cloned()).unwrap_or_default()]),
+            Value::Null => Ok(vec![Value::Null]),
+            v => Err(anyhow!("Cannot index {} with a number", type_name(&v))),
+        }),
+        Expr::Slice(inner, start, end) => each(inner, &|value| {
+            let range = |len: usize| {
+                let clamp = |i: i64| if i < 0 { (len as i64 + i).max(0) as usize } else { (i as usize).min(len) };
+                let start = start.map_or(0, clamp);
+                start..end.map_or(len, clamp).max(start)
+            };
+            match value {
+                Value::Array(list) => Ok(vec![Value::Array(list[range(list.len())].to_vec())]),
+                Value::String(s) => {
+                    let chars: Vec<char> = s.chars().collect();
+                    Ok(vec![Value::String(chars[range(chars.len())].iter().collect())])
+                }
+                Value::Null => Ok(vec![Value::Null]),
+                v => Err(anyhow!("Cannot slice {}", type_name(&v))),
+            }
+        }),
+        Expr::Iterate(inner) => each(inner, &iterate),
+        Expr::Keys => match input {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                Ok(vec![Value::from(keys.into_iter().cloned().collect::<Vec<_>>())])
+            }
+            Value::Array(list) => Ok(vec![Value::from((0..list.len()).collect::<Vec<_>>())]),
+            v => Err(anyhow!("{} has no keys", type_name(v))),
+        },
+        Expr::Length => match input {
+            Value::Null => Ok(vec![Value::from(0)]),
+            Value::Bool(_) => Err(anyhow!("boolean has no length")),
+            // 整数的绝对值仍然是整数
+            Value::Number(n) => Ok(vec![match (n.as_u64(), n.as_i64()) {
+                (Some(_), _) => Value::Number(n.clone()),
+                (_, Some(i)) => Value::from(i.unsigned_abs()),
+                _ => Value::from(n.as_f64().unwrap_or_default().abs()),
+            }]),
+            Value::String(s) => Ok(vec![Value::from(s.chars().count())]),
+            Value::Array(list) => Ok(vec![Value::from(list.len())]),
+            Value::Object(map) => Ok(vec![Value::from(map.len())]),
+        },
+        Expr::Map(f) => {
+            let mut output = Vec::new();
+            for value in iterate(input.clone())? {
+                output.extend(eval(f, &value)?);
+            }
+            Ok(vec![Value::Array(output)])
+        }
+        Expr::Select(cond) => {
+            let outputs = eval(cond, input)?;
+            Ok(outputs.iter().filter(|v| truthy(v)).map(|_| input.clone()).collect())
+        }
+        Expr::Pipe(lhs, rhs) => each(lhs, &|value| eval(rhs, &value)),
+        Expr::Compare(lhs, op, rhs) => {
+            let rhs = eval(rhs, input)?;
+            each(lhs, &|l| {
+                Ok(rhs
+                    .iter()
+                    .map(|r| {
+                        let ordering = compare(&l, r);
+                        Value::Bool(match op {
+                            Op::Eq => ordering == Ordering::Equal,
+                            Op::Ne => ordering != Ordering::Equal,
+                            Op::Lt => ordering == Ordering::Less,
+                            Op::Le => ordering != Ordering::Greater,
+                            Op::Gt => ordering == Ordering::Greater,
+                            Op::Ge => ordering != Ordering::Less,
+                        })
+                    })
+                    .collect())
+            })
+        }
+    }
+}
+
+fn iterate(value: Value) -> Result<Vec<Value>> {
+    match value {
+        Value::Array(list) => Ok(list),
+        Value::Object(map) => Ok(map.into_iter().map(|(_, v)| v).collect()),
+        v => Err(anyhow!("Cannot iterate over {}", type_name(&v))),
+    }
+}
+
+/// 负数下标从末尾开始数
+fn position(i: i64, len: usize) -> Option<usize> {
+    usize::try_from(if i < 0 { len as i64 + i } else { i }).ok()
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// jq 的排序规则：null < false < true < 数字 < 字符串 < 数组 < 对象
+fn compare(a: &Value, b: &Value) -> Ordering {
+    let rank = |v: &Value| match v {
+        Value::Null => 0,
+        Value::Bool(false) => 1,
+        Value::Bool(true) => 2,
+        Value::Number(_) => 3,
+        Value::String(_) => 4,
+        Value::Array(_) => 5,
+        Value::Object(_) => 6,
+    };
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| compare(a, b))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(_), Value::Object(_)) if a == b => Ordering::Equal,
+        (Value::Object(_), Value::Object(_)) => a.to_string().cmp(&b.to_string()),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(filter: &str) -> Vec<Value> {
+        let value = json!({
+            "users": [
+                {"name": "ann", "age": 31, "tags": ["a", "b"]},
+                {"name": "bob", "age": 25, "tags": []},
+                {"name": "cat", "age": 40, "tags": ["c"]}
+            ],
+            "total name": 3
+        });
+        parse(filter).unwrap().apply(&value).unwrap()
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(run(".users[0].name"), vec![json!("ann")]);
+        assert_eq!(run(".users[-1].name"), vec![json!("cat")]);
+        assert_eq!(run(".users[5]"), vec![Value::Null]);
+        assert_eq!(run(".\"total name\""), vec![json!(3)]);
+        assert_eq!(run(".[\"total name\"]"), vec![json!(3)]);
+        assert_eq!(run(".users[1:] | map(.name)"), vec![json!(["bob", "cat"])]);
+        assert_eq!(run(".users[:1] | length"), vec![json!(1)]);
+        assert_eq!(run(".users[0].name[1:]"), vec![json!("nn")]);
+        assert_eq!(run(".users[].name"), vec![json!("ann"), json!("bob"), json!("cat")]);
+        assert_eq!(run(".users | .[] | .age"), vec![json!(31), json!(25), json!(40)]);
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(run("keys"), vec![json!(["total name", "users"])]);
+        assert_eq!(run(".users | keys"), vec![json!([0, 1, 2])]);
+        assert_eq!(run(".users | length"), vec![json!(3)]);
+        assert_eq!(run(".users[0].age | length"), vec![json!(31)]);
+        let length = |value: Value| parse("length").unwrap().apply(&value).unwrap();
+        assert_eq!(length(json!(-2)), vec![json!(2)]);
+        assert_eq!(length(json!(-1.5)), vec![json!(1.5)]);
+        assert_eq!(run(".users | map(.tags | length)"), vec![json!([2, 0, 1])]);
+        assert_eq!(run(".users[] | select(.age > 30) | .name"), vec![json!("ann"), json!("cat")]);
+        assert_eq!(run(".users | map(select(.name != \"bob\")) | length"), vec![json!(2)]);
+        assert_eq!(run(".users[0].age >= 31"), vec![json!(true)]);
+        assert_eq!(run("."), run("(.)"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse(".users[").is_err());
+        assert!(parse("sort").is_err());
+        assert!(parse(".a ? .b").is_err());
+        assert!(parse("\"x").is_err());
+        let value = json!({"n": 1});
+        assert!(parse(".n.a").unwrap().apply(&value).is_err());
+        assert!(parse(".n[]").unwrap().apply(&value).is_err());
+    }
+}
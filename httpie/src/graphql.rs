@@ -0,0 +1,103 @@
+use std::fs;
+use anyhow::{Result, anyhow};
+use serde_json::{Map, Value};
+use crate::items::KvItem;
+
+/// 把查询和变量转换成 GraphQL 的标准请求体 `{"query", "variables", "operationName"}`
+///
+/// `field=value` 是字符串变量，`field:=json` 是任意类型的变量，请求头和查询参数保持不变
+pub fn envelope(query: &str, operation: Option<&str>, items: &[KvItem]) -> Result<Vec<KvItem>> {
+    let query = match query.strip_prefix('@') {
+        Some(path) => fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path, e))?,
+        None => query.to_string(),
+    };
+    if query.trim().is_empty() {
+        return Err(anyhow!("The GraphQL query is empty"));
+    }
+    let mut variables = Map::new();
+    let mut envelope = Vec::new();
+    for item in items {
+        match item {
+            KvItem::Data(k, v) => {
+                variables.insert(k.clone(), Value::String(v.clone()));
+            }
+            KvItem::Json(k, v) => {
+                variables.insert(k.clone(), v.clone());
+            }
+            KvItem::File(k, _) => return Err(anyhow!("GraphQL variable {} cannot be a file upload", k)),
+            item => envelope.push(item.clone()),
+        }
+    }
+    envelope.push(KvItem::Json("query".into(), Value::String(query)));
+    if !variables.is_empty() {
+        envelope.push(KvItem::Json("variables".into(), Value::Object(variables)));
+    }
+    if let Some(operation) = operation {
+        envelope.push(KvItem::Json("operationName".into(), Value::String(operation.into())));
+    }
+    Ok(envelope)
+}
+
+/// 一条错误占一行，带上出错的位置和字段路径
+pub fn format_error(error: &Value) -> String {
+    let message = match error.get("message").and_then(Value::as_str) {
+        Some(message) => message.to_string(),
+        None => return error.to_string(),
+    };
+    let mut details = Vec::new();
+    if let Some(locations) = error.get("locations").and_then(Value::as_array) {
+        let locations: Vec<String> = locations
+            .iter()
+            .filter_map(|l| Some(format!("{}:{}", l.get("line")?, l.get("column")?)))
+            .collect();
+        if !locations.is_empty() {
+            details.push(format!("at {}", locations.join(", ")));
+        }
+    }
+    if let Some(path) = error.get("path").and_then(Value::as_array) {
+        let path: Vec<String> = path
+            .iter()
+            .map(|p| p.as_str().map(str::to_string).unwrap_or_else(|| p.to_string()))
+            .collect();
+        details.push(format!("path {}", path.join(".")));
+    }
+    match details.is_empty() {
+        true => message,
+        false => format!("{} ({})", message, details.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_envelope() {
+        let items: Vec<KvItem> = ["Authorization:Bearer x", "name=ann", "limit:=10", "tags:=[\"a\"]"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let items = envelope("query Users { users { id } }", Some("Users"), &items).unwrap();
+        assert_eq!(items[0], KvItem::Header("Authorization".into(), "Bearer x".into()));
+        let body = crate::items::json_body(&items).unwrap();
+        assert_eq!(
+            Value::Object(body),
+            json!({
+                "query": "query Users { users { id } }",
+                "variables": {"name": "ann", "limit": 10, "tags": ["a"]},
+                "operationName": "Users",
+            })
+        );
+        assert!(envelope(" ", None, &[]).is_err());
+        assert!(envelope("{ a }", None, &[KvItem::File("f".into(), "a.txt".into())]).is_err());
+    }
+
+    #[test]
+    fn test_format_error() {
+        let error = json!({"message": "Not found", "locations": [{"line": 1, "column": 9}], "path": ["user", 0, "name"]});
+        assert_eq!(format_error(&error), "Not found (at 1:9, path user.0.name)");
+        assert_eq!(format_error(&json!({"message": "Denied"})), "Denied");
+        assert_eq!(format_error(&json!("oops")), "\"oops\"");
+    }
+}
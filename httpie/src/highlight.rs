@@ -0,0 +1,85 @@
+use std::sync::OnceLock;
+use anyhow::{Result, anyhow};
+use mime::Mime;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+// 加载内置的语法和主题比较慢，`--stream` 和 `--filter` 会多次高亮，所以只加载一次
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+/// 根据 Content-Type 找到对应语法的扩展名，不支持的类型返回 `None`
+pub fn syntax_for(m: &Mime) -> Option<&'static str> {
+    let subtype = m.subtype().as_str();
+    match (m.type_().as_str(), subtype) {
+        (_, "json") => Some("json"),
+        (_, "html") => Some("html"),
+        (_, "xml") => Some("xml"),
+        (_, "javascript") | (_, "ecmascript") => Some("js"),
+        ("text", "css") => Some("css"),
+        (_, "yaml") | (_, "x-yaml") => Some("yaml"),
+        // application/problem+json、application/atom+xml 这类带后缀的类型
+        _ => match m.suffix().map(|s| s.as_str()) {
+            Some("json") => Some("json"),
+            Some("xml") => Some("xml"),
+            Some("yaml") => Some("yaml"),
+            _ => None,
+        },
+    }
+}
+
+/// 用指定的主题把 body 渲染成带终端颜色的文本
+pub fn highlight(body: &str, ext: &str, theme: &str) -> Result<String> {
+    let ss = syntax_set();
+    let ts = theme_set();
+    let syntax = ss.find_syntax_by_extension(ext).unwrap_or_else(|| ss.find_syntax_plain_text());
+    let theme = ts.themes.get(theme).ok_or_else(|| anyhow!("Unknown theme {}", theme))?;
+    let mut h = HighlightLines::new(syntax, theme);
+    let mut output = String::new();
+    for line in LinesWithEndings::from(body) {
+        let ranges = h.highlight(line, ss);
+        output.push_str(&as_24_bit_terminal_escaped(&ranges[..], false));
+    }
+    // 重置终端颜色，避免影响后续输出
+    output.push_str("\x1b[0m");
+    Ok(output)
+}
+
+/// 校验 `--style` 参数是否是内置的主题
+pub fn parse_theme(s: &str) -> Result<String> {
+    let ts = theme_set();
+    if ts.themes.contains_key(s) {
+        return Ok(s.into());
+    }
+    let themes: Vec<&str> = ts.themes.keys().map(|k| k.as_str()).collect();
+    Err(anyhow!("Unknown theme {}, available themes: {}", s, themes.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_for() {
+        assert_eq!(syntax_for(&mime::APPLICATION_JSON), Some("json"));
+        assert_eq!(syntax_for(&"application/problem+json".parse().unwrap()), Some("json"));
+        assert_eq!(syntax_for(&mime::TEXT_HTML_UTF_8), Some("html"));
+        assert_eq!(syntax_for(&"text/xml".parse().unwrap()), Some("xml"));
+        assert_eq!(syntax_for(&mime::APPLICATION_JAVASCRIPT), Some("js"));
+        assert_eq!(syntax_for(&mime::TEXT_CSS), Some("css"));
+        assert_eq!(syntax_for(&"application/x-yaml".parse().unwrap()), Some("yaml"));
+        assert_eq!(syntax_for(&mime::TEXT_PLAIN), None);
+        assert_eq!(syntax_for(&mime::IMAGE_PNG), None);
+    }
+}
@@ -0,0 +1,223 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use anyhow::{Result, anyhow};
+use reqwest::multipart::{Form, Part};
+use serde_json::{Map, Value};
+
+/// 命令行上的请求项，语法与 HTTPie 保持一致
+#[derive(Debug, Clone, PartialEq)]
+pub enum KvItem {
+    /// `Header:value`
+    Header(String, String),
+    /// `param==value`
+    Query(String, String),
+    /// `field=value`，以及 `field=@file` 读入的文本
+    Data(String, String),
+    /// `field:=<raw json>`，以及 `field:=@file.json` 读入的 JSON
+    Json(String, Value),
+    /// `field@path`，只能用于 `--form` 和 `--multipart`
+    File(String, PathBuf),
+}
+
+// 同一位置上更长的分隔符优先匹配，例如 `:=@` 要先于 `:=` 和 `:`
+const SEPARATORS: [&str; 7] = [":=@", "==", ":=", "=@", "=", ":", "@"];
+
+impl FromStr for KvItem {
+    type Err = anyhow::Error;
+    // 找到最靠前的分隔符，根据分隔符决定请求项的类型
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || anyhow!(format!("Failed to parse {}", s));
+        let (pos, sep) = s
+            .char_indices()
+            .find_map(|(i, _)| SEPARATORS.iter().find(|sep| s[i..].starts_with(*sep)).map(|sep| (i, *sep)))
+            .ok_or_else(err)?;
+        let key = &s[..pos];
+        if key.is_empty() {
+            return Err(err());
+        }
+        let key = key.to_string();
+        let value = &s[pos + sep.len()..];
+        let item = match sep {
+            ":" => Self::Header(key, value.trim().to_string()),
+            "==" => Self::Query(key, value.to_string()),
+            "=" => Self::Data(key, value.to_string()),
+            "=@" => Self::Data(key, read_file(value)?),
+            ":=" => Self::Json(key, parse_json(value)?),
+            "@" => Self::File(key, value.into()),
+            _ => Self::Json(key, parse_json(&read_file(value)?)?),
+        };
+        Ok(item)
+    }
+}
+
+fn read_file(path: &str) -> Result<String> {
+    fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path, e))
+}
+
+fn parse_json(s: &str) -> Result<Value> {
+    serde_json::from_str(s).map_err(|e| anyhow!("Invalid JSON {}: {}", s, e))
+}
+
+/// 所有 `Header:value` 请求项
+pub fn headers(items: &[KvItem]) -> Vec<(&str, &str)> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            KvItem::Header(k, v) => Some((k.as_str(), v.as_str())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 所有 `param==value` 请求项
+pub fn query(items: &[KvItem]) -> Vec<(&str, &str)> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            KvItem::Query(k, v) => Some((k.as_str(), v.as_str())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 把数据项组装成 JSON 对象，没有数据项时返回 `None`
+pub fn json_body(items: &[KvItem]) -> Option<Map<String, Value>> {
+    let mut body = Map::new();
+    for item in items {
+        match item {
+            KvItem::Data(k, v) => {
+                body.insert(k.clone(), Value::String(v.clone()));
+            }
+            KvItem::Json(k, v) => {
+                body.insert(k.clone(), v.clone());
+            }
+            _ => {}
+        }
+    }
+    if body.is_empty() { None } else { Some(body) }
+}
+
+/// 是否包含会放到请求 body 里的数据项
+pub fn has_data(items: &[KvItem]) -> bool {
+    items
+        .iter()
+        .any(|item| matches!(item, KvItem::Data(..) | KvItem::Json(..) | KvItem::File(..)))
+}
+
+/// 是否包含 `field@path` 文件上传项
+pub fn has_files(items: &[KvItem]) -> bool {
+    items.iter().any(|item| matches!(item, KvItem::File(..)))
+}
+
+/// 把数据项组装成 `application/x-www-form-urlencoded` 表单
+pub fn form_body(items: &[KvItem]) -> Result<Vec<(&str, &str)>> {
+    let mut body = Vec::new();
+    for item in items {
+        match item {
+            KvItem::Data(k, v) => body.push((k.as_str(), v.as_str())),
+            KvItem::Json(k, _) => return Err(anyhow!("JSON item {} is not supported in form mode", k)),
+            KvItem::File(k, _) => return Err(anyhow!("File item {} requires --multipart", k)),
+            _ => {}
+        }
+    }
+    Ok(body)
+}
+
+/// 把数据项和文件项组装成 `multipart/form-data` 表单
+pub fn multipart_body(items: &[KvItem]) -> Result<Form> {
+    let mut form = Form::new();
+    for item in items {
+        match item {
+            KvItem::Data(k, v) => form = form.text(k.clone(), v.clone()),
+            KvItem::File(k, path) => form = form.part(k.clone(), file_part(path)?),
+            KvItem::Json(k, _) => return Err(anyhow!("JSON item {} is not supported in multipart mode", k)),
+            _ => {}
+        }
+    }
+    Ok(form)
+}
+
+fn file_part(path: &Path) -> Result<Part> {
+    let data = fs::read(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    let mut part = Part::bytes(data).mime_str(mime.as_ref())?;
+    if let Some(name) = path.file_name() {
+        part = part.file_name(name.to_string_lossy().into_owned());
+    }
+    Ok(part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_items() {
+        assert_eq!("key=value".parse::<KvItem>().unwrap(), KvItem::Data("key".into(), "value".into()));
+        assert_eq!("page==2".parse::<KvItem>().unwrap(), KvItem::Query("page".into(), "2".into()));
+        assert_eq!(
+            "X-API-Token: abc".parse::<KvItem>().unwrap(),
+            KvItem::Header("X-API-Token".into(), "abc".into())
+        );
+        assert_eq!(
+            "tags:=[1, true]".parse::<KvItem>().unwrap(),
+            KvItem::Json("tags".into(), json!([1, true]))
+        );
+        assert_eq!(
+            "Referer:http://a.com/?x=1".parse::<KvItem>().unwrap(),
+            KvItem::Header("Referer".into(), "http://a.com/?x=1".into())
+        );
+        assert_eq!(
+            "avatar@./me.png".parse::<KvItem>().unwrap(),
+            KvItem::File("avatar".into(), "./me.png".into())
+        );
+        assert_eq!(
+            "email=a@b.com".parse::<KvItem>().unwrap(),
+            KvItem::Data("email".into(), "a@b.com".into())
+        );
+        assert!("key".parse::<KvItem>().is_err());
+        assert!("=value".parse::<KvItem>().is_err());
+        assert!("count:=abc".parse::<KvItem>().is_err());
+    }
+
+    #[test]
+    fn test_parse_file_items() {
+        let path = std::env::temp_dir().join("httpie_items_test.json");
+        fs::write(&path, r#"{"a": 1}"#).unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(
+            format!("raw=@{}", path).parse::<KvItem>().unwrap(),
+            KvItem::Data("raw".into(), r#"{"a": 1}"#.into())
+        );
+        assert_eq!(
+            format!("obj:=@{}", path).parse::<KvItem>().unwrap(),
+            KvItem::Json("obj".into(), json!({"a": 1}))
+        );
+        assert!("raw=@/no/such/file".parse::<KvItem>().is_err());
+    }
+
+    #[test]
+    fn test_json_body() {
+        let items: Vec<KvItem> = vec!["a=1".parse().unwrap(), "b:=1".parse().unwrap(), "c==1".parse().unwrap()];
+        assert_eq!(Value::Object(json_body(&items).unwrap()), json!({"a": "1", "b": 1}));
+        assert!(json_body(&items[2..]).is_none());
+        assert_eq!(query(&items), vec![("c", "1")]);
+    }
+
+    #[test]
+    fn test_form_body() {
+        let items: Vec<KvItem> = vec!["a=1".parse().unwrap(), "c==1".parse().unwrap()];
+        assert_eq!(form_body(&items).unwrap(), vec![("a", "1")]);
+        assert!(!has_files(&items));
+        assert!(has_data(&items));
+        assert!(!has_data(&items[1..]));
+
+        let items: Vec<KvItem> = vec!["a:=1".parse().unwrap()];
+        assert!(form_body(&items).is_err());
+        let items: Vec<KvItem> = vec!["f@/no/such/file".parse().unwrap()];
+        assert!(has_files(&items));
+        assert!(multipart_body(&items).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+/// JSONPath 的一段
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// `.name` 或 `['name']`
+    Key(String),
+    /// `[0]`，负数从末尾开始数
+    Index(i64),
+    /// `.*` 或 `[*]`
+    Wildcard,
+    /// `..name`，任意深度的同名字段
+    Descendant(String),
+}
+
+/// 支持 JSONPath 的一个常用子集：`$.a.b`、`$['a']`、`$.list[0]`、`$.list[-1]`、`$.list[*].id` 和 `$..id`
+pub fn select<'a>(value: &'a Value, path: &str) -> Result<Vec<&'a Value>> {
+    let mut current = vec![value];
+    for segment in parse(path)? {
+        current = current.into_iter().flat_map(|v| apply(v, &segment)).collect();
+    }
+    Ok(current)
+}
+
+fn apply<'a>(value: &'a Value, segment: &Segment) -> Vec<&'a Value> {
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+        (Segment::Index(i), Value::Array(list)) => {
+            let i = if *i < 0 { list.len() as i64 + i } else { *i };
+            usize::try_from(i).ok().and_then(|i| list.get(i)).into_iter().collect()
+        }
+        (Segment::Wildcard, Value::Array(list)) => list.iter().collect(),
+        (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+        (Segment::Descendant(key), _) => {
+            let mut found = Vec::new();
+            descendants(value, key, &mut found);
+            found
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn descendants<'a>(value: &'a Value, key: &str, found: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(v) = map.get(key) {
+                found.push(v);
+            }
+            map.values().for_each(|v| descendants(v, key, found));
+        }
+        Value::Array(list) => list.iter().for_each(|v| descendants(v, key, found)),
+        _ => {}
+    }
+}
+
+fn parse(path: &str) -> Result<Vec<Segment>> {
+    let err = || anyhow!("Invalid JSONPath {}", path);
+    let mut rest = path.trim().strip_prefix('$').ok_or_else(err)?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix("..") {
+            let (name, r) = split_name(r);
+            if name.is_empty() {
+                return Err(err());
+            }
+            segments.push(Segment::Descendant(name.into()));
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('.') {
+            let (name, r) = split_name(r);
+            segments.push(match name {
+                "" => return Err(err()),
+                "*" => Segment::Wildcard,
+                name => Segment::Key(name.into()),
+            });
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let (inner, r) = r.split_once(']').ok_or_else(err)?;
+            let inner = inner.trim();
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            segments.push(match (inner, quoted) {
+                ("*", _) => Segment::Wildcard,
+                (_, Some(key)) => Segment::Key(key.into()),
+                (index, None) => Segment::Index(index.parse().map_err(|_| err())?),
+            });
+            rest = r;
+        } else {
+            return Err(err());
+        }
+    }
+    Ok(segments)
+}
+
+/// 字段名到下一个 `.` 或 `[` 为止
+fn split_name(s: &str) -> (&str, &str) {
+    let end = s.find(['.', '[']).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select() {
+        let value = json!({"data": {"users": [{"id": 1, "name": "a"}, {"id": 2, "tags": {"id": 3}}]}, "my key": true});
+        let ids = |path| select(&value, path).unwrap().into_iter().cloned().collect::<Vec<_>>();
+        assert_eq!(ids("$"), vec![value.clone()]);
+        assert_eq!(ids("$.data.users[0].name"), vec![json!("a")]);
+        assert_eq!(ids("$.data.users[-1].id"), vec![json!(2)]);
+        assert_eq!(ids("$.data.users[*].id"), vec![json!(1), json!(2)]);
+        assert_eq!(ids("$['my key']"), vec![json!(true)]);
+        assert_eq!(ids("$..id"), vec![json!(1), json!(2), json!(3)]);
+        assert!(ids("$.data.missing[0]").is_empty());
+        assert!(select(&value, "data.users").is_err());
+        assert!(select(&value, "$.data[x]").is_err());
+        assert!(select(&value, "$.").is_err());
+    }
+}
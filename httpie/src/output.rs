@@ -0,0 +1,372 @@
+use std::io::{self, Write};
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use encoding_rs::{Encoding, UTF_8};
+use mime::Mime;
+use reqwest::{header, Request, Response};
+use tokio_tungstenite::tungstenite::handshake::client::Response as WsResponse;
+use crate::stream::{Event, Lines, SseParser};
+use crate::filter::Filter;
+use crate::{graphql, highlight, tls};
+
+const BINARY_NOTICE: &str = "+-----------------------------------------+
+| NOTE: binary data not shown in terminal |
++-----------------------------------------+";
+
+// 这些 application/* 类型的内容一定是二进制的，其他类型再根据内容判断
+const BINARY_SUBTYPES: [&str; 14] = [
+    "octet-stream", "zip", "gzip", "x-gzip", "x-tar", "x-bzip2", "x-7z-compressed", "pdf",
+    "protobuf", "x-protobuf", "vnd.google.protobuf", "grpc", "wasm", "msgpack",
+];
+
+/// 要输出的内容，对应 `--print` 里的 `HBhb`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    pub request_headers: bool,
+    pub request_body: bool,
+    pub response_headers: bool,
+    pub response_body: bool,
+    pub style: String,
+    /// stdout 是终端时才高亮，二进制内容只在重定向时输出
+    pub tty: bool,
+    /// 跟随跳转时打印中间的每一个请求和响应
+    pub all: bool,
+    /// 打印对端证书的摘要
+    pub tls: bool,
+    /// `--filter`，只打印 JSON body 里选出来的部分
+    pub filter: Option<Filter>,
+}
+
+impl Output {
+    pub fn new(print: &str, style: &str, tty: bool) -> Self {
+        Self {
+            request_headers: print.contains('H'),
+            request_body: print.contains('B'),
+            response_headers: print.contains('h'),
+            response_body: print.contains('b'),
+            style: style.into(),
+            tty,
+            all: false,
+            tls: false,
+            filter: None,
+        }
+    }
+
+    /// 按实际发送的内容打印请求
+    pub fn print_request(&self, request: &Request) -> Result<()> {
+        if self.request_headers {
+            let url = request.url();
+            let path = match url.query() {
+                Some(q) => format!("{}?{}", url.path(), q),
+                None => url.path().to_string(),
+            };
+            let line = format!("{} {} {:?}", request.method(), path, request.version()).blue();
+            println!("{}", line);
+            let host = match (url.host_str(), url.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (host, _) => host.unwrap_or_default().to_string(),
+            };
+            println!("{}: {:?}", "host".green(), host);
+            print_headers(request.headers());
+        }
+        if self.request_body {
+            if let Some(body) = request.body() {
+                match body.as_bytes() {
+                    Some(bytes) => self.print_bytes(get_content_type(request.headers()), bytes)?,
+                    None => println!("(streaming body is not shown)"),
+                }
+                println!();
+            }
+        }
+        Ok(())
+    }
+
+    /// 打印响应的状态行和响应头
+    pub fn print_head(&self, resp: &Response) {
+        if let Some(summary) = tls::peer_summary(resp).filter(|_| self.tls) {
+            // 证书信息是诊断用的，输出到 stderr，不混进响应内容；
+            // reqwest 的 TlsInfo 只提供对端证书，拿不到协商出来的 TLS 版本
+            eprintln!("{}\n{}\n", "Peer certificate".blue(), summary);
+        }
+        if self.response_headers {
+            print_status(resp);
+            print_headers(resp.headers());
+        }
+    }
+
+    pub async fn print_response(&self, resp: Response) -> Result<()> {
+        self.print_head(&resp);
+        if !self.response_body {
+            return Ok(());
+        }
+        let mime = get_content_type(resp.headers());
+        let body = resp.bytes().await?;
+        self.print_content(mime, &body)
+    }
+
+    /// 打印已经读出来的响应 body
+    pub fn print_content(&self, mime: Option<Mime>, body: &[u8]) -> Result<()> {
+        // HEAD 请求以及 204 等响应没有 body
+        if !self.response_body || body.is_empty() {
+            return Ok(());
+        }
+        match &self.filter {
+            Some(filter) => self.print_filtered(filter, body),
+            None => self.print_bytes(mime, body),
+        }
+    }
+
+    /// 每一个结果单独格式化成 JSON
+    fn print_filtered(&self, filter: &Filter, body: &[u8]) -> Result<()> {
+        let value: serde_json::Value =
+            serde_json::from_slice(body).map_err(|_| anyhow!("--filter requires a JSON response body"))?;
+        for result in filter.apply(&value)? {
+            print_body(Some(mime::APPLICATION_JSON), &result.to_string(), self.tty.then_some(self.style.as_str()));
+        }
+        Ok(())
+    }
+
+    /// `--stream`：边收边打印，SSE 按事件打印，NDJSON 每一行单独格式化
+    pub async fn print_stream(&self, mut resp: Response) -> Result<()> {
+        self.print_head(&resp);
+        if !self.response_body {
+            return Ok(());
+        }
+        let mime = get_content_type(resp.headers());
+        let style = self.tty.then_some(self.style.as_str());
+        let sse = mime.as_ref().is_some_and(|m| m.essence_str() == "text/event-stream");
+        if !sse && !mime.as_ref().is_some_and(is_json_lines) {
+            let json = mime.as_ref().is_some_and(|m| m.subtype() == mime::JSON || m.suffix() == Some(mime::JSON));
+            if json || self.filter.is_some() {
+                // 普通 JSON 只有一个文档，读完整个 body 再格式化和过滤
+                let body = resp.bytes().await?;
+                return self.print_content(mime, &body);
+            }
+            // 其他类型原样输出每一个数据块
+            while let Some(chunk) = resp.chunk().await? {
+                if self.tty && is_binary(mime.as_ref(), &chunk) {
+                    println!("{}", BINARY_NOTICE.yellow());
+                    return Ok(());
+                }
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(&chunk)?;
+                stdout.flush()?;
+            }
+            return Ok(());
+        }
+        let mut lines = Lines::default();
+        let mut parser = SseParser::default();
+        let mut print_line = |line: String| -> Result<()> {
+            if sse {
+                if let Some(event) = parser.feed(&line) {
+                    print_event(&event, style);
+                }
+            } else if !line.trim().is_empty() {
+                self.print_content(Some(mime::APPLICATION_JSON), line.as_bytes())?;
+            }
+            Ok(())
+        };
+        while let Some(chunk) = resp.chunk().await? {
+            lines.push(&chunk).into_iter().try_for_each(&mut print_line)?;
+        }
+        lines.finish().into_iter().try_for_each(&mut print_line)?;
+        if let Some(event) = parser.finish() {
+            print_event(&event, style);
+        }
+        Ok(())
+    }
+
+    /// 分开打印 GraphQL 响应里的 `data` 和 `errors`，错误用红色输出到 stderr，返回是否有错误
+    pub fn print_graphql(&self, mime: Option<Mime>, body: &[u8]) -> Result<bool> {
+        let value = match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(value @ serde_json::Value::Object(_)) => value,
+            // 不是 GraphQL 响应，例如网关返回的错误页面
+            _ => {
+                if self.response_body && !body.is_empty() {
+                    self.print_bytes(mime, body)?;
+                }
+                return Ok(false);
+            }
+        };
+        let errors = value["errors"].as_array().map(Vec::as_slice).unwrap_or_default();
+        if !value["data"].is_null() {
+            self.print_content(Some(mime::APPLICATION_JSON), value["data"].to_string().as_bytes())?;
+        }
+        for error in errors {
+            eprintln!("{}", format!("GraphQL error: {}", graphql::format_error(error)).red());
+        }
+        Ok(!errors.is_empty())
+    }
+
+    /// 打印 WebSocket 握手的响应
+    pub fn print_upgrade(&self, resp: &WsResponse) {
+        if self.response_headers {
+            println!("{}\n", format!("{:?} {}", resp.version(), resp.status()).blue());
+            print_headers(resp.headers());
+        }
+    }
+
+    /// 打印收到的 WebSocket 消息，文本消息是 JSON 时会被格式化
+    pub fn print_message(&self, data: &[u8], binary: bool) -> Result<()> {
+        if !self.response_body {
+            return Ok(());
+        }
+        let mime = if binary {
+            mime::APPLICATION_OCTET_STREAM
+        } else if serde_json::from_slice::<serde_json::Value>(data).is_ok() {
+            mime::APPLICATION_JSON
+        } else {
+            mime::TEXT_PLAIN_UTF_8
+        };
+        self.print_bytes(Some(mime), data)
+    }
+
+    /// 文本按 charset 解码后打印，二进制在终端上只显示提示，重定向时原样输出
+    fn print_bytes(&self, mime: Option<Mime>, body: &[u8]) -> Result<()> {
+        if is_binary(mime.as_ref(), body) {
+            if self.tty {
+                println!("{}", BINARY_NOTICE.yellow());
+            } else {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(body)?;
+                stdout.flush()?;
+            }
+            return Ok(());
+        }
+        let text = decode(mime.as_ref(), body);
+        print_body(mime, &text, self.tty.then_some(self.style.as_str()));
+        Ok(())
+    }
+}
+
+/// 校验 `--print` 参数，只允许 `HBhb` 中的字符
+pub fn parse_print(s: &str) -> Result<String> {
+    match s.chars().find(|c| !"HBhb".contains(*c)) {
+        Some(c) => Err(anyhow!("Invalid print option {}, expected any of HBhb", c)),
+        None => Ok(s.into()),
+    }
+}
+
+fn print_status(resp: &Response) {
+    let status = format!("{:?} {}", resp.version(), resp.status()).blue();
+    println!("{}\n", status);
+}
+
+fn print_headers(headers: &header::HeaderMap) {
+    for (name, value) in headers {
+        println!("{}: {:?}", name.to_string().green(), value);
+    }
+    println!();
+}
+
+fn print_body(m: Option<Mime>, body: &str, style: Option<&str>) {
+    let ext = m.as_ref().and_then(highlight::syntax_for);
+    let pretty = match ext {
+        Some("json") => jsonxf::pretty_print(body).unwrap_or_else(|_| body.to_string()),
+        _ => body.to_string(),
+    };
+    match ext.zip(style).map(|(ext, style)| highlight::highlight(&pretty, ext, style)) {
+        Some(Ok(v)) => println!("{}", v),
+        _ => println!("{}", pretty),
+    }
+}
+
+fn print_event(event: &Event, style: Option<&str>) {
+    if let Some(name) = &event.event {
+        println!("{}: {}", "event".green(), name);
+    }
+    if let Some(id) = &event.id {
+        println!("{}: {}", "id".green(), id);
+    }
+    let data = event.data.join("\n");
+    let json = serde_json::from_str::<serde_json::Value>(&data).is_ok();
+    print_body(json.then_some(mime::APPLICATION_JSON), &data, style);
+    println!();
+}
+
+/// 每一行是一个 JSON 文档的流，例如 NDJSON 和 JSON Lines
+fn is_json_lines(m: &Mime) -> bool {
+    matches!(m.subtype().as_str(), "x-ndjson" | "ndjson" | "jsonl" | "x-jsonlines" | "json-seq")
+}
+
+fn is_binary(mime: Option<&Mime>, body: &[u8]) -> bool {
+    let mut charset = false;
+    if let Some(m) = mime {
+        let binary = match m.type_().as_str() {
+            // image/svg+xml 是文本
+            "image" => m.suffix() != Some(mime::XML),
+            "audio" | "video" | "font" => true,
+            "application" => BINARY_SUBTYPES.contains(&m.subtype().as_str()),
+            _ => false,
+        };
+        if binary {
+            return true;
+        }
+        charset = m.get_param(mime::CHARSET).is_some();
+    }
+    let head = &body[..body.len().min(1024)];
+    if head.contains(&0) {
+        return true;
+    }
+    // 声明了 charset 的交给解码处理，否则要求是合法的 UTF-8，截断在末尾的多字节字符不算错误
+    !charset && matches!(std::str::from_utf8(head), Err(e) if e.error_len().is_some())
+}
+
+fn decode(mime: Option<&Mime>, body: &[u8]) -> String {
+    let encoding = mime
+        .and_then(|m| m.get_param(mime::CHARSET))
+        .and_then(|charset| Encoding::for_label(charset.as_str().as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+pub fn get_content_type(headers: &header::HeaderMap) -> Option<Mime> {
+    headers.get(header::CONTENT_TYPE).and_then(|ct| ct.to_str().ok()?.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_print() {
+        assert!(parse_print("HBhb").is_ok());
+        assert!(parse_print("hx").is_err());
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(is_binary(Some(&mime::IMAGE_PNG), b"abc"));
+        assert!(is_binary(Some(&"application/x-protobuf".parse().unwrap()), b"abc"));
+        assert!(!is_binary(Some(&"image/svg+xml".parse().unwrap()), b"<svg/>"));
+        assert!(!is_binary(Some(&mime::APPLICATION_JSON), b"{}"));
+        assert!(is_binary(None, b"\x1f\x8b\x08\x00"));
+        assert!(is_binary(None, b"\xff\xfe\xfd"));
+        assert!(!is_binary(None, "你好".as_bytes()));
+        // 截断在多字节字符中间仍然是文本
+        assert!(!is_binary(None, &"你好".as_bytes()[..4]));
+        assert!(!is_binary(Some(&"text/html; charset=gbk".parse().unwrap()), b"\xc4\xe3\xba\xc3"));
+    }
+
+    #[test]
+    fn test_is_json_lines() {
+        assert!(is_json_lines(&"application/x-ndjson".parse().unwrap()));
+        assert!(is_json_lines(&"application/jsonl; charset=utf-8".parse().unwrap()));
+        assert!(!is_json_lines(&mime::APPLICATION_JSON));
+        assert!(!is_json_lines(&"application/problem+json".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_decode() {
+        let gbk: Mime = "text/plain; charset=gbk".parse().unwrap();
+        assert_eq!(decode(Some(&gbk), b"\xc4\xe3\xba\xc3"), "你好");
+        assert_eq!(decode(None, "你好".as_bytes()), "你好");
+    }
+
+    #[test]
+    fn test_output_new() {
+        let output = Output::new("Hb", "x", true);
+        assert!(output.request_headers && output.response_body);
+        assert!(!output.request_body && !output.response_headers);
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+use anyhow::{Result, anyhow};
+use reqwest::{header, Method, Request, Response, StatusCode, Url};
+use crate::session;
+
+/// 跳转次数超过 `--max-redirects`
+#[derive(Debug)]
+pub struct TooManyRedirects(pub usize);
+
+impl fmt::Display for TooManyRedirects {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Too many redirects (--max-redirects={})", self.0)
+    }
+}
+
+impl std::error::Error for TooManyRedirects {}
+
+/// 3xx 响应里要跳转到的地址，不需要跳转时返回 `None`
+pub fn location(resp: &Response) -> Option<Url> {
+    if !resp.status().is_redirection() {
+        return None;
+    }
+    let location = resp.headers().get(header::LOCATION)?.to_str().ok()?;
+    resp.url().join(location).ok()
+}
+
+/// 响应里所有的 `Set-Cookie`
+pub fn set_cookies(resp: &Response) -> Vec<String> {
+    resp.headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok().map(str::to_string))
+        .collect()
+}
+
+/// 把跳转响应设置的 cookie 合并到下一个请求的 `Cookie` 里，同名的 cookie 以新的为准
+fn merge_cookies(headers: &mut header::HeaderMap, set_cookies: &[String]) {
+    let mut cookies: Vec<(String, String)> = headers
+        .get(header::COOKIE)
+        .and_then(|v| v.to_str().ok())
+        .map(|cookie| {
+            cookie
+                .split(';')
+                .filter_map(|pair| pair.trim().split_once('='))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    for (name, value) in set_cookies.iter().filter_map(|v| session::parse_set_cookie(v)) {
+        cookies.retain(|(k, _)| k != name);
+        if let Some(value) = value {
+            cookies.push((name.to_string(), value.to_string()));
+        }
+    }
+    let cookie: Vec<String> = cookies.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    match header::HeaderValue::from_str(&cookie.join("; ")) {
+        Ok(value) if !cookies.is_empty() => {
+            headers.insert(header::COOKIE, value);
+        }
+        _ => {
+            headers.remove(header::COOKIE);
+        }
+    }
+}
+
+/// 发送请求前保存的一份副本，用来构造跳转之后的请求
+pub struct Redirect {
+    method: Method,
+    url: Url,
+    headers: header::HeaderMap,
+    request: Option<Request>,
+}
+
+impl Redirect {
+    pub fn new(request: &Request) -> Self {
+        Self {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            request: request.try_clone(),
+        }
+    }
+
+    /// 307/308 保留原来的方法和 body，其他跳转改成不带 body 的 GET，
+    /// 同一站点内会带上跳转响应设置的 cookie，例如登录网关的 302
+    pub fn follow(self, status: StatusCode, location: Url, set_cookies: &[String]) -> Result<Request> {
+        let keep = self.method == Method::GET
+            || self.method == Method::HEAD
+            || status == StatusCode::TEMPORARY_REDIRECT
+            || status == StatusCode::PERMANENT_REDIRECT;
+        let mut next = if keep {
+            self.request
+                .ok_or_else(|| anyhow!("Cannot resend a streaming body to {}", location))?
+        } else {
+            let mut next = Request::new(Method::GET, location.clone());
+            *next.headers_mut() = self.headers;
+            next.headers_mut().remove(header::CONTENT_TYPE);
+            next.headers_mut().remove(header::CONTENT_LENGTH);
+            next
+        };
+        // 跳转到其他站点时不能带上认证信息和 cookie
+        if location.origin() != self.url.origin() {
+            next.headers_mut().remove(header::AUTHORIZATION);
+            next.headers_mut().remove(header::COOKIE);
+        } else {
+            merge_cookies(next.headers_mut(), set_cookies);
+        }
+        *next.url_mut() = location;
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: Method) -> Request {
+        let mut request = Request::new(method, "http://a.com/login".parse().unwrap());
+        request.headers_mut().insert(header::AUTHORIZATION, "Basic x".parse().unwrap());
+        request.headers_mut().insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        *request.body_mut() = Some("{}".into());
+        request
+    }
+
+    #[test]
+    fn test_follow() {
+        let next = Redirect::new(&request(Method::POST))
+            .follow(StatusCode::FOUND, "http://a.com/home".parse().unwrap(), &[])
+            .unwrap();
+        assert_eq!(next.method(), Method::GET);
+        assert!(next.body().is_none());
+        assert!(next.headers().get(header::CONTENT_TYPE).is_none());
+        assert!(next.headers().get(header::AUTHORIZATION).is_some());
+
+        let next = Redirect::new(&request(Method::POST))
+            .follow(StatusCode::TEMPORARY_REDIRECT, "http://b.com/login".parse().unwrap(), &[])
+            .unwrap();
+        assert_eq!(next.method(), Method::POST);
+        assert!(next.body().is_some());
+        assert_eq!(next.url().as_str(), "http://b.com/login");
+        assert!(next.headers().get(header::AUTHORIZATION).is_none());
+    }
+
+    #[test]
+    fn test_follow_cookies() {
+        let mut login = request(Method::POST);
+        login.headers_mut().insert(header::COOKIE, "theme=dark; sid=old; csrf=1".parse().unwrap());
+        let set_cookies = ["sid=new; Path=/; HttpOnly".to_string(), "csrf=; Max-Age=0".to_string()];
+        let next = Redirect::new(&login)
+            .follow(StatusCode::FOUND, "http://a.com/home".parse().unwrap(), &set_cookies)
+            .unwrap();
+        assert_eq!(next.headers().get(header::COOKIE).unwrap(), "theme=dark; sid=new");
+
+        let next = Redirect::new(&request(Method::GET))
+            .follow(StatusCode::FOUND, "http://a.com/home".parse().unwrap(), &set_cookies[..1])
+            .unwrap();
+        assert_eq!(next.headers().get(header::COOKIE).unwrap(), "sid=new");
+
+        // 其他站点拿不到这些 cookie
+        let next = Redirect::new(&login)
+            .follow(StatusCode::FOUND, "http://b.com/home".parse().unwrap(), &set_cookies)
+            .unwrap();
+        assert!(next.headers().get(header::COOKIE).is_none());
+    }
+}
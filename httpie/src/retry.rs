@@ -0,0 +1,84 @@
+use std::time::{Duration, SystemTime};
+use reqwest::{header, Method, Response};
+
+const BASE_DELAY: Duration = Duration::from_millis(500);
+pub const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// 重试策略：连接错误、超时以及指定的状态码会被重试
+#[derive(Debug, Clone, Default)]
+pub struct Retry {
+    pub retries: usize,
+    pub statuses: Vec<u16>,
+}
+
+impl Retry {
+    pub fn should_retry_status(&self, resp: &Response) -> bool {
+        self.statuses.contains(&resp.status().as_u16())
+    }
+}
+
+/// 连接失败是可以重试的临时错误；超时时请求可能已经发出去了，只有幂等的方法才重试
+pub fn is_transient(err: &anyhow::Error, method: &Method) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .map(|e| e.is_connect() || (e.is_timeout() && method.is_idempotent()))
+        .unwrap_or(false)
+}
+
+/// 第 `attempt` 次重试前等待的时间，指数增长并带上随机抖动
+pub fn backoff(attempt: u32) -> Duration {
+    delay(attempt, rand::random::<f64>())
+}
+
+fn delay(attempt: u32, jitter: f64) -> Duration {
+    let base = BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_DELAY);
+    // 在 [base / 2, base) 之间随机取值，避免多个客户端同时重试
+    base.mul_f64(0.5 + jitter / 2.0)
+}
+
+/// 解析 `Retry-After`，支持秒数和 HTTP 日期两种格式
+pub fn retry_after(resp: &Response) -> Option<Duration> {
+    let value = resp.headers().get(header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, SystemTime::now())
+}
+
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay() {
+        assert_eq!(delay(0, 0.0), Duration::from_millis(250));
+        assert_eq!(delay(2, 1.0), Duration::from_secs(2));
+        assert_eq!(delay(20, 1.0), MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_is_transient() {
+        // 只建立连接不回应，请求必然超时
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(100)).build().unwrap();
+        let err = client.post(url).send().await.unwrap_err().into();
+        assert!(is_transient(&err, &Method::GET));
+        assert!(!is_transient(&err, &Method::POST));
+        assert!(!is_transient(&anyhow::anyhow!("invalid header"), &Method::GET));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:29:00 GMT", now), Some(Duration::from_secs(60)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
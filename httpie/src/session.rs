@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use anyhow::{Result, anyhow};
+use reqwest::{header, Url};
+use serde::{Deserialize, Serialize};
+use crate::auth::Auth;
+
+/// 持久化的会话，保存请求头、cookie 和认证信息
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cookies: BTreeMap<String, String>,
+    #[serde(default)]
+    pub auth: Option<Auth>,
+}
+
+impl Session {
+    /// 加载会话，名字里带路径分隔符时直接当作文件路径，
+    /// 否则按 host 存放在配置目录下，文件不存在时返回空会话
+    pub fn load(name: &str, url: &str) -> Result<Self> {
+        let path = if name.contains(std::path::MAIN_SEPARATOR) {
+            PathBuf::from(name)
+        } else {
+            session_dir(url)?.join(format!("{}.json", name))
+        };
+        let mut session: Session = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Invalid session file {}: {}", path.display(), e))?,
+            Err(_) => Session::default(),
+        };
+        session.path = path;
+        Ok(session)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 会话里的请求头和 cookie，作为客户端的缺省请求头
+    pub fn default_headers(&self) -> Result<header::HeaderMap> {
+        let mut headers = header::HeaderMap::new();
+        for (name, value) in &self.headers {
+            headers.insert(header::HeaderName::from_bytes(name.as_bytes())?, value.parse()?);
+        }
+        if !self.cookies.is_empty() {
+            let cookie: Vec<String> = self.cookies.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            headers.insert(header::COOKIE, cookie.join("; ").parse()?);
+        }
+        Ok(headers)
+    }
+
+    /// 记录本次请求带上的请求头，和请求内容相关的头不保存
+    pub fn update_headers<'a>(&mut self, headers: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        for (name, value) in headers {
+            let lower = name.to_ascii_lowercase();
+            if lower.starts_with("content-") || lower.starts_with("if-") || lower == "cookie" {
+                continue;
+            }
+            self.headers.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    /// 按顺序应用响应里的 `Set-Cookie`，跟随跳转时包括中间每一跳的响应
+    pub fn update_cookies(&mut self, set_cookies: &[String]) {
+        for value in set_cookies {
+            self.set_cookie(value);
+        }
+    }
+
+    fn set_cookie(&mut self, value: &str) {
+        match parse_set_cookie(value) {
+            Some((name, Some(value))) => self.cookies.insert(name.to_string(), value.to_string()),
+            Some((name, None)) => self.cookies.remove(name),
+            None => None,
+        };
+    }
+}
+
+/// 解析 `Set-Cookie` 的名字和值，服务器要求删除的 cookie 值为 `None`
+pub fn parse_set_cookie(value: &str) -> Option<(&str, Option<&str>)> {
+    let mut parts = value.split(';').map(|s| s.trim());
+    let (name, value) = parts.next()?.split_once('=')?;
+    let (mut max_age, mut expires) = (None, None);
+    for (k, v) in parts.filter_map(|attr| attr.split_once('=')) {
+        if k.eq_ignore_ascii_case("max-age") {
+            max_age = v.parse::<i64>().ok();
+        } else if k.eq_ignore_ascii_case("expires") {
+            // 也接受 `01-Jan-1970` 这种常见的写法
+            expires = httpdate::parse_http_date(&v.replace('-', " ")).ok();
+        }
+    }
+    // Max-Age 优先于 Expires，小于等于 0 或者已经过期表示服务器要求删除这个 cookie
+    let expired = match max_age {
+        Some(max_age) => max_age <= 0,
+        None => expires.is_some_and(|t| t <= SystemTime::now()),
+    };
+    Some((name, (!expired).then_some(value)))
+}
+
+fn session_dir(url: &str) -> Result<PathBuf> {
+    let url: Url = url.parse()?;
+    let mut host = url.host_str().ok_or_else(|| anyhow!("Missing host in {}", url))?.to_string();
+    if let Some(port) = url.port() {
+        host = format!("{}_{}", host, port);
+    }
+    let dir = dirs::config_dir().ok_or_else(|| anyhow!("Cannot find the config directory"))?;
+    Ok(dir.join("httpie-rs").join("sessions").join(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_cookie() {
+        let mut session = Session::default();
+        session.set_cookie("sid=abc; Path=/; HttpOnly");
+        session.set_cookie("theme=dark");
+        assert_eq!(session.cookies.get("sid").unwrap(), "abc");
+        session.set_cookie("sid=; Max-Age=0");
+        assert!(!session.cookies.contains_key("sid"));
+        session.set_cookie("sid=abc; Expires=Fri, 01 Jan 2100 00:00:00 GMT");
+        assert_eq!(session.cookies.get("sid").unwrap(), "abc");
+        session.set_cookie("sid=; Expires=Thu, 01 Jan 1970 00:00:00 GMT");
+        assert!(!session.cookies.contains_key("sid"));
+        session.set_cookie("sid=abc");
+        session.set_cookie("sid=; expires=Thu, 01-Jan-1970 00:00:00 GMT; Path=/");
+        assert!(!session.cookies.contains_key("sid"));
+        session.set_cookie("sid=abc; Max-Age=60; Expires=Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(session.cookies.get("sid").unwrap(), "abc");
+        session.set_cookie("sid=; Max-Age=0");
+
+        let headers = session.default_headers().unwrap();
+        assert_eq!(headers.get(header::COOKIE).unwrap(), "theme=dark");
+
+        // 跳转链上每一跳的 cookie 按顺序生效
+        session.update_cookies(&["sid=1".into(), "lang=en".into(), "sid=; Max-Age=0".into()]);
+        assert_eq!(session.cookies.len(), 2);
+        assert_eq!(session.cookies.get("lang").unwrap(), "en");
+    }
+
+    #[test]
+    fn test_update_headers() {
+        let mut session = Session::default();
+        session.update_headers(vec![("X-Token", "1"), ("Content-Type", "text/plain"), ("If-Match", "x")]);
+        assert_eq!(session.headers.len(), 1);
+        assert_eq!(session.headers.get("X-Token").unwrap(), "1");
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join("httpie_session_test").join("s.json");
+        let name = path.to_str().unwrap();
+        let mut session = Session::load(name, "http://localhost").unwrap();
+        session.set_cookie("sid=abc");
+        session.save().unwrap();
+        let session = Session::load(name, "http://localhost").unwrap();
+        assert_eq!(session.cookies.get("sid").unwrap(), "abc");
+    }
+}
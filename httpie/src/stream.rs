@@ -0,0 +1,95 @@
+/// 把陆续收到的字节切成完整的行，不完整的最后一行留到下一次
+#[derive(Debug, Default)]
+pub struct Lines {
+    buf: Vec<u8>,
+}
+
+impl Lines {
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buf.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            lines.push(to_line(&line[..pos]));
+        }
+        lines
+    }
+
+    /// 连接关闭时剩下的内容
+    pub fn finish(self) -> Option<String> {
+        (!self.buf.is_empty()).then(|| to_line(&self.buf))
+    }
+}
+
+fn to_line(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// server-sent events 里的一个事件
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub event: Option<String>,
+    pub id: Option<String>,
+    pub data: Vec<String>,
+}
+
+/// 按行解析 SSE，空行表示一个事件结束
+#[derive(Debug, Default)]
+pub struct SseParser {
+    event: Event,
+}
+
+impl SseParser {
+    pub fn feed(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            let event = std::mem::take(&mut self.event);
+            return (event != Event::default()).then_some(event);
+        }
+        // 冒号开头的是注释，通常用来保持连接
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value).to_string();
+        match field {
+            "event" => self.event.event = Some(value),
+            "id" => self.event.id = Some(value),
+            "data" => self.event.data.push(value),
+            _ => {}
+        }
+        None
+    }
+
+    /// 连接关闭时还没有以空行结束的事件
+    pub fn finish(self) -> Option<Event> {
+        (self.event != Event::default()).then_some(self.event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let mut lines = Lines::default();
+        assert_eq!(lines.push(b"a\r\nb"), vec!["a"]);
+        assert_eq!(lines.push(b"c\n\nd"), vec!["bc", ""]);
+        assert_eq!(lines.finish().as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn test_sse_parser() {
+        let mut parser = SseParser::default();
+        let events: Vec<Event> = [": ping", "event: update", "id:7", "data: {\"a\":1}", "data: x", "", "", "data"]
+            .iter()
+            .filter_map(|line| parser.feed(line))
+            .collect();
+        assert_eq!(
+            events,
+            vec![Event { event: Some("update".into()), id: Some("7".into()), data: vec!["{\"a\":1}".into(), "x".into()] }]
+        );
+        assert_eq!(parser.finish().unwrap().data, vec![""]);
+    }
+}
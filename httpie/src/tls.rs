@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use reqwest::tls::{self, TlsInfo};
+use reqwest::{Certificate, ClientBuilder, Identity, Response};
+use sha2::{Digest, Sha256};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// `--verify` 参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verify {
+    Yes,
+    No,
+    /// 只信任这个 PEM 文件里的 CA 证书
+    Bundle(PathBuf),
+}
+
+pub fn parse_verify(s: &str) -> Result<Verify> {
+    match s.to_ascii_lowercase().as_str() {
+        "yes" | "true" => Ok(Verify::Yes),
+        "no" | "false" => Ok(Verify::No),
+        _ => Ok(Verify::Bundle(s.into())),
+    }
+}
+
+/// `--ssl` 参数，rustls 只支持 TLS 1.2 和 1.3
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SslVersion {
+    #[clap(name = "tls1.2")]
+    Tls12,
+    #[clap(name = "tls1.3")]
+    Tls13,
+}
+
+/// 按命令行参数配置证书校验、客户端证书和最低 TLS 版本
+pub fn configure(
+    mut builder: ClientBuilder,
+    verify: &Verify,
+    cert: Option<&Path>,
+    cert_key: Option<&Path>,
+    ssl: Option<SslVersion>,
+) -> Result<ClientBuilder> {
+    match verify {
+        Verify::Yes => {}
+        Verify::No => builder = builder.danger_accept_invalid_certs(true),
+        Verify::Bundle(path) => {
+            builder = builder.tls_built_in_root_certs(false);
+            for cert in Certificate::from_pem_bundle(&read(path)?)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+    }
+    if let Some(cert) = cert {
+        // 私钥可以和证书放在同一个 PEM 文件里
+        let mut pem = read(cert)?;
+        if let Some(key) = cert_key {
+            pem.push(b'\n');
+            pem.extend(read(key)?);
+        }
+        builder = builder.identity(Identity::from_pem(&pem)?);
+    }
+    if let Some(ssl) = ssl {
+        builder = builder.min_tls_version(match ssl {
+            SslVersion::Tls12 => tls::Version::TLS_1_2,
+            SslVersion::Tls13 => tls::Version::TLS_1_3,
+        });
+    }
+    Ok(builder.tls_info(true))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))
+}
+
+/// 对端证书的摘要，不是 HTTPS 连接时返回 `None`
+pub fn peer_summary(resp: &Response) -> Option<String> {
+    let der = resp.extensions().get::<TlsInfo>()?.peer_certificate()?;
+    Some(cert_summary(der))
+}
+
+fn cert_summary(der: &[u8]) -> String {
+    let fingerprint: Vec<String> = Sha256::digest(der).iter().map(|b| format!("{:02X}", b)).collect();
+    let fingerprint = fingerprint.join(":");
+    match X509Certificate::from_der(der) {
+        Ok((_, cert)) => format!(
+            "subject: {}\nissuer: {}\nvalid: {} to {}\nsha256: {}",
+            cert.subject(),
+            cert.issuer(),
+            cert.validity().not_before,
+            cert.validity().not_after,
+            fingerprint
+        ),
+        Err(_) => format!("sha256: {}", fingerprint),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBozCCAUmgAwIBAgIUI+f+I1FuJK/q2TyvDaFe67teA8swCgYIKoZIzj0EAwIw
+JzEUMBIGA1UEAwwLaHR0cGllLnRlc3QxDzANBgNVBAoMBkh0dHBpZTAeFw0yNjEw
+MTYxNTE2MDFaFw0zNjEwMTMxNTE2MDFaMCcxFDASBgNVBAMMC2h0dHBpZS50ZXN0
+MQ8wDQYDVQQKDAZIdHRwaWUwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAATtdaKl
+/WG8+C33IkCcMuiHvMKAYyLKRFX1Qdm/9kHchOGwc0M1/a2lrYsuEhQ8bMWhF1XT
+AEtqQbW3YnFf6/u5o1MwUTAdBgNVHQ4EFgQUWwagiIWhupPwd1pNgYZqKp2eAG8w
+HwYDVR0jBBgwFoAUWwagiIWhupPwd1pNgYZqKp2eAG8wDwYDVR0TAQH/BAUwAwEB
+/zAKBggqhkjOPQQDAgNIADBFAiEAoCEa/gWcA4+wTqCNJsaVnT/4wUSxCVW/yLYV
+h5o9Bg8CIFNyUdCXm2Akzv7VOlMVu+aFEK++tijD/WX0M1jAGWMR
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_parse_verify() {
+        assert_eq!(parse_verify("no").unwrap(), Verify::No);
+        assert_eq!(parse_verify("YES").unwrap(), Verify::Yes);
+        assert_eq!(parse_verify("ca.pem").unwrap(), Verify::Bundle("ca.pem".into()));
+    }
+
+    #[test]
+    fn test_cert_summary() {
+        let (_, pem) = x509_parser::pem::parse_x509_pem(CERT.as_bytes()).unwrap();
+        let summary = cert_summary(&pem.contents);
+        assert!(summary.contains("subject: CN=httpie.test, O=Httpie"));
+        assert!(summary.contains("sha256: 6D:76:46:DD:9C:3C"));
+        assert!(summary.contains("2036"));
+    }
+}
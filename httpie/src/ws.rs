@@ -0,0 +1,119 @@
+use std::io::BufRead;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use futures_util::{SinkExt, StreamExt};
+use reqwest::{Request, Url};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use crate::output::Output;
+
+/// 完成 WebSocket 握手后发送消息并打印收到的消息，直到服务器关闭连接
+///
+/// 请求头、查询参数和认证信息都来自 `build` 构造好的 HTTP 请求。
+/// `messages` 发送完之后按行发送 stdin，stdin 结束时主动关闭连接，`timeout` 只用于握手
+pub async fn connect(
+    request: Request,
+    messages: &[String],
+    stdin: bool,
+    timeout: Option<Duration>,
+    output: &Output,
+) -> Result<()> {
+    let url = ws_url(request.url())?;
+    let mut upgrade = url.as_str().into_client_request()?;
+    for (name, value) in request.headers() {
+        upgrade.headers_mut().append(name, value.clone());
+    }
+    let handshake = tokio_tungstenite::connect_async(upgrade);
+    let result = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, handshake)
+            .await
+            .map_err(|e| anyhow::Error::new(e).context("WebSocket handshake timed out"))?,
+        None => handshake.await,
+    };
+    let (socket, resp) = match result {
+        Ok(v) => v,
+        Err(Error::Http(resp)) => {
+            output.print_upgrade(&resp);
+            return Err(anyhow!("WebSocket handshake failed with HTTP {}", resp.status()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    output.print_upgrade(&resp);
+
+    let (mut sink, mut stream) = socket.split();
+    for message in messages {
+        sink.send(Message::Text(message.clone())).await?;
+    }
+    let mut lines = stdin.then(read_lines);
+    if lines.is_none() && !messages.is_empty() {
+        sink.send(Message::Close(None)).await?;
+    }
+    loop {
+        tokio::select! {
+            line = next_line(&mut lines) => match line {
+                Some(line) => sink.send(Message::Text(line)).await?,
+                None => {
+                    sink.send(Message::Close(None)).await?;
+                    lines = None;
+                }
+            },
+            frame = stream.next() => match frame {
+                Some(Ok(Message::Text(text))) => output.print_message(text.as_bytes(), false)?,
+                Some(Ok(Message::Binary(data))) => output.print_message(&data, true)?,
+                // ping/pong 和关闭握手由 tungstenite 处理
+                Some(Ok(_)) => {}
+                Some(Err(Error::ConnectionClosed)) | None => break,
+                Some(Err(e)) => return Err(e.into()),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// `http(s)://` 换成对应的 `ws(s)://`，这样 `:3000/chat` 之类的简写也能用
+fn ws_url(url: &Url) -> Result<Url> {
+    let scheme = match url.scheme() {
+        "http" | "ws" => "ws",
+        "https" | "wss" => "wss",
+        scheme => return Err(anyhow!("Unsupported WebSocket scheme {}", scheme)),
+    };
+    let mut url = url.clone();
+    url.set_scheme(scheme).map_err(|_| anyhow!("Invalid WebSocket URL {}", url))?;
+    Ok(url)
+}
+
+/// tokio 的 stdin 读取无法取消，会让程序在退出时卡住，所以放在单独的线程里读
+fn read_lines() -> mpsc::UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// stdin 已经读完时永远不返回，这样 `select!` 只会等待服务器的消息
+async fn next_line(lines: &mut Option<mpsc::UnboundedReceiver<String>>) -> Option<String> {
+    match lines {
+        Some(lines) => lines.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_url() {
+        let url = |s: &str| ws_url(&s.parse().unwrap()).map(|url| url.to_string());
+        assert_eq!(url("http://localhost:3000/chat?a=1").unwrap(), "ws://localhost:3000/chat?a=1");
+        assert_eq!(url("https://a.com/").unwrap(), "wss://a.com/");
+        assert_eq!(url("wss://a.com/").unwrap(), "wss://a.com/");
+        assert!(url("ftp://a.com/").is_err());
+    }
+}